@@ -0,0 +1,86 @@
+use anoto_dots::anoto_6x6_a4_fixed;
+use anoto_dots::bitmatrix::BitMatrix;
+use anoto_dots::lookup::{WindowIndex, find_linear};
+use std::time::Instant;
+
+// Decodes every 6x6 window of a large pattern and reports the throughput
+fn main() {
+    let codec = anoto_6x6_a4_fixed();
+    let bitmatrix = codec.encode_bitmatrix((256, 256), (10, 2));
+
+    let windows: Vec<((i32, i32), BitMatrix)> = (0..250usize)
+        .flat_map(|y| (0..250usize).map(move |x| (x, y)))
+        .map(|(x, y)| ((x as i32, y as i32), bitmatrix.window((x, y), (6, 6))))
+        .collect();
+
+    let start = Instant::now();
+    for (expected, window) in &windows {
+        let pos = codec.decode_position(window).expect("window decodes");
+        assert_eq!(pos, *expected);
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "decode_position: {} windows in {:.1?} ({:.0} windows/s)",
+        windows.len(),
+        elapsed,
+        windows.len() as f64 / elapsed.as_secs_f64()
+    );
+
+    let start = Instant::now();
+    for (pos, window) in &windows {
+        codec.decode_section(window, *pos).expect("section decodes");
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "decode_section: {} windows in {:.1?} ({:.0} windows/s)",
+        windows.len(),
+        elapsed,
+        windows.len() as f64 / elapsed.as_secs_f64()
+    );
+
+    // Table lookups against the linear scan they replaced, for the MNS
+    // columns of every window and the SNS windows of the first sequence
+    let spec = codec.to_spec("anoto");
+    let columns: Vec<Vec<i8>> = windows.iter()
+        .map(|(_, window)| (0..6).map(|y| window.x_bit(y, 0)).collect())
+        .collect();
+    let sns = &spec.sns[0];
+    let sns_windows: Vec<Vec<i8>> = (0..columns.len())
+        .map(|i| (0..5).map(|j| sns[(i * 7 + j) % sns.len()]).collect())
+        .collect();
+
+    for (name, seq, order, needles) in [("MNS", &spec.mns, 6, &columns), ("SNS", sns, 5, &sns_windows)] {
+        let index = WindowIndex::new(seq, order).expect("table fits");
+
+        let start = Instant::now();
+        let indexed: Vec<Vec<usize>> = needles.iter().map(|n| index.find_all(n)).collect();
+        let indexed_time = start.elapsed();
+
+        let start = Instant::now();
+        let scanned: Vec<Vec<usize>> = needles.iter().map(|n| find_linear(seq, n)).collect();
+        let scanned_time = start.elapsed();
+
+        assert_eq!(indexed, scanned);
+        println!(
+            "{} lookup: {} needles, index {:.1?}, linear scan {:.1?} ({:.1}x faster)",
+            name,
+            needles.len(),
+            indexed_time,
+            scanned_time,
+            scanned_time.as_secs_f64() / indexed_time.as_secs_f64()
+        );
+    }
+
+    // Runs on all cores with the parallel feature
+    let batch: Vec<BitMatrix> = windows.iter().map(|(_, window)| window.clone()).collect();
+    let start = Instant::now();
+    let results = codec.decode_batch(&batch);
+    let elapsed = start.elapsed();
+    assert!(results.iter().all(|r| r.is_ok()));
+    println!(
+        "decode_batch: {} windows in {:.1?} ({:.0} windows/s)",
+        batch.len(),
+        elapsed,
+        batch.len() as f64 / elapsed.as_secs_f64()
+    );
+}
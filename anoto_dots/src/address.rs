@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Global coordinate of a dot across all sections. Along each axis the
+// section and the position within it are packed as
+// section * section_size + position, see AnotoCodec::dot_address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct DotAddress {
+    pub x: u64,
+    pub y: u64,
+}
+
+impl DotAddress {
+    pub fn new(x: u64, y: u64) -> Self {
+        DotAddress { x, y }
+    }
+
+    // The address dx dots to the right and dy dots down
+    pub fn offset(self, dx: u64, dy: u64) -> Self {
+        DotAddress {
+            x: self.x + dx,
+            y: self.y + dy,
+        }
+    }
+}
+
+impl fmt::Display for DotAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
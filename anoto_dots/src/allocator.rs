@@ -0,0 +1,175 @@
+use crate::AnotoCodec;
+use crate::address::DotAddress;
+use crate::layout::PatternRect;
+use serde::{Serialize, Deserialize};
+use std::error::Error;
+use std::fmt;
+use crate::persist::{read_json, write_json_atomic};
+use std::fs;
+
+// Reasons an allocation is refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AllocationError {
+    DuplicateId { id: String },
+    Overlap { id: String, existing: String },
+    EmptyRegion,
+    OutsidePatternSpace { rect: PatternRect },
+    SpaceExhausted { shape: (usize, usize) },
+}
+
+impl fmt::Display for AllocationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AllocationError::DuplicateId { id } => write!(f, "Allocation {} already exists", id),
+            AllocationError::Overlap { id, existing } => {
+                write!(f, "Allocation {} overlaps allocation {}", id, existing)
+            }
+            AllocationError::EmptyRegion => write!(f, "Allocations must have at least one dot"),
+            AllocationError::OutsidePatternSpace { rect } => write!(
+                f,
+                "Region {}x{} at {} does not fit into a single section",
+                rect.width, rect.height, rect.origin
+            ),
+            AllocationError::SpaceExhausted { shape } => {
+                write!(f, "No free region of {}x{} dots left", shape.1, shape.0)
+            }
+        }
+    }
+}
+
+impl Error for AllocationError {}
+
+// A region of the pattern space handed out under a unique id
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Allocation {
+    pub id: String,
+    pub rect: PatternRect,
+}
+
+// Hands out non-overlapping regions of the pattern space of a codec, so that
+// no two printed pages share dots. Regions never span sections. New regions
+// are packed left to right into shelves as tall as their first region, and
+// shelves fill a section from the top before moving on to the next section.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatternAllocator {
+    section_size: u64,
+    section_count: u64,
+    allocations: Vec<Allocation>,
+    cursor: DotAddress,
+    shelf_height: u64,
+}
+
+impl PatternAllocator {
+    pub fn new(codec: &AnotoCodec) -> Self {
+        PatternAllocator {
+            section_size: codec.section_size(),
+            section_count: codec.section_count() as u64,
+            allocations: Vec::new(),
+            cursor: DotAddress::new(0, 0),
+            shelf_height: 0,
+        }
+    }
+
+    pub fn allocations(&self) -> &[Allocation] {
+        &self.allocations
+    }
+
+    pub fn get(&self, id: &str) -> Option<&PatternRect> {
+        self.allocations.iter().find(|a| a.id == id).map(|a| &a.rect)
+    }
+
+    // Assign the next free region of the given (rows, columns) shape
+    pub fn allocate(&mut self, id: &str, shape: (usize, usize)) -> Result<PatternRect, AllocationError> {
+        self.check_id(id)?;
+        let (height, width) = (shape.0 as u64, shape.1 as u64);
+        if width == 0 || height == 0 {
+            return Err(AllocationError::EmptyRegion);
+        }
+        if width > self.section_size || height > self.section_size {
+            return Err(AllocationError::SpaceExhausted { shape });
+        }
+
+        loop {
+            let size = self.section_size;
+            let (mut x, mut y) = (self.cursor.x, self.cursor.y);
+
+            // Start a new shelf when the region does not fit beside the last
+            // one, and a new section when the shelf does not fit below
+            if x % size + width > size {
+                x -= x % size;
+                y += self.shelf_height;
+                self.shelf_height = 0;
+            }
+            let section_row = self.cursor.y / size;
+            if y / size != section_row || y % size + height > size {
+                let (sx, sy) = (x / size + 1, section_row);
+                (x, y) = if sx < self.section_count { (sx * size, sy * size) } else { (0, (sy + 1) * size) };
+                self.shelf_height = 0;
+            }
+            if y / size >= self.section_count {
+                return Err(AllocationError::SpaceExhausted { shape });
+            }
+
+            let rect = PatternRect { origin: DotAddress::new(x, y), width, height };
+            match self.allocations.iter().find(|a| a.rect.overlaps(&rect)) {
+                // Skip past regions reserved explicitly
+                Some(a) => {
+                    self.cursor = DotAddress::new(a.rect.origin.x + a.rect.width, y);
+                    self.shelf_height = self.shelf_height.max(height);
+                }
+                None => {
+                    self.cursor = DotAddress::new(x + width, y);
+                    self.shelf_height = self.shelf_height.max(height);
+                    self.allocations.push(Allocation { id: id.to_string(), rect });
+                    return Ok(rect);
+                }
+            }
+        }
+    }
+
+    // Record a region chosen by the caller, e.g. one printed before the
+    // allocator was used
+    pub fn reserve(&mut self, id: &str, rect: PatternRect) -> Result<(), AllocationError> {
+        self.check_id(id)?;
+        if rect.width == 0 || rect.height == 0 {
+            return Err(AllocationError::EmptyRegion);
+        }
+        let size = self.section_size;
+        let fits = |start: u64, len: u64| start / size < self.section_count && start % size + len <= size;
+        if !fits(rect.origin.x, rect.width) || !fits(rect.origin.y, rect.height) {
+            return Err(AllocationError::OutsidePatternSpace { rect });
+        }
+        if let Some(a) = self.allocations.iter().find(|a| a.rect.overlaps(&rect)) {
+            return Err(AllocationError::Overlap { id: id.to_string(), existing: a.id.clone() });
+        }
+
+        self.allocations.push(Allocation { id: id.to_string(), rect });
+        Ok(())
+    }
+
+    fn check_id(&self, id: &str) -> Result<(), AllocationError> {
+        if self.get(id).is_some() {
+            return Err(AllocationError::DuplicateId { id: id.to_string() });
+        }
+        Ok(())
+    }
+
+    // Load an allocation table saved as JSON
+    pub fn load(filename: &str) -> Result<PatternAllocator, Box<dyn Error>> {
+        read_json(filename)
+    }
+
+    // Load the allocation table, or start an empty one if the file does not exist
+    pub fn open(filename: &str, codec: &AnotoCodec) -> Result<PatternAllocator, Box<dyn Error>> {
+        match fs::metadata(filename) {
+            Ok(_) => Self::load(filename),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::new(codec)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // Save the allocation table as JSON, replacing the file atomically
+    pub fn save(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        write_json_atomic(filename, self)
+    }
+}
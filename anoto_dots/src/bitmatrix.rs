@@ -0,0 +1,178 @@
+use crate::ERASED;
+use ndarray::{Array2, Array3, s};
+use std::error::Error;
+use std::fmt;
+
+// Displacement of a dot from its grid point. A dot encodes the value
+// x_bit + (y_bit << 1), which selects Up, Right, Left or Down in that order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dot {
+    Up,
+    Right,
+    Left,
+    Down,
+}
+
+impl Dot {
+    pub fn from_bits(x_bit: i8, y_bit: i8) -> Option<Dot> {
+        match (x_bit, y_bit) {
+            (0, 0) => Some(Dot::Up),
+            (1, 0) => Some(Dot::Right),
+            (0, 1) => Some(Dot::Left),
+            (1, 1) => Some(Dot::Down),
+            _ => None,
+        }
+    }
+
+    // The (x-bit, y-bit) pair encoded by this dot
+    pub fn bits(self) -> (i8, i8) {
+        match self {
+            Dot::Up => (0, 0),
+            Dot::Right => (1, 0),
+            Dot::Left => (0, 1),
+            Dot::Down => (1, 1),
+        }
+    }
+
+    // Unit offset from the grid point in image coordinates, with x to the
+    // right and y downwards
+    pub fn displacement(self) -> (i32, i32) {
+        match self {
+            Dot::Up => (0, -1),
+            Dot::Left => (-1, 0),
+            Dot::Right => (1, 0),
+            Dot::Down => (0, 1),
+        }
+    }
+
+    // The direction after turning the paper a quarter counter-clockwise
+    pub fn rotate_ccw(self) -> Dot {
+        match self {
+            Dot::Up => Dot::Left,
+            Dot::Left => Dot::Down,
+            Dot::Down => Dot::Right,
+            Dot::Right => Dot::Up,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BitMatrixError {
+    WrongChannels { channels: usize },
+    InvalidValue { y: usize, x: usize, bit: usize, value: i8 },
+}
+
+impl fmt::Display for BitMatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BitMatrixError::WrongChannels { channels } => {
+                write!(f, "Expected (M,N,2) matrix, got {} channels", channels)
+            }
+            BitMatrixError::InvalidValue { y, x, bit, value } => {
+                write!(f, "Value {} at [{}, {}, {}] is neither a bit nor erased", value, y, x, bit)
+            }
+        }
+    }
+}
+
+impl Error for BitMatrixError {}
+
+// Grid of dots stored as an (M,N,2) array of [y, x, bit], where bit 0 is the
+// x-bit and bit 1 the y-bit. Every value is 0, 1 or ERASED.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitMatrix {
+    bits: Array3<i8>,
+}
+
+impl BitMatrix {
+    pub fn new(bits: Array3<i8>) -> Result<Self, BitMatrixError> {
+        let channels = bits.dim().2;
+        if channels != 2 {
+            return Err(BitMatrixError::WrongChannels { channels });
+        }
+        if let Some(((y, x, bit), &value)) = bits.indexed_iter().find(|&(_, &v)| v != 0 && v != 1 && v != ERASED) {
+            return Err(BitMatrixError::InvalidValue { y, x, bit, value });
+        }
+        Ok(BitMatrix { bits })
+    }
+
+    // Matrix from dot directions, with None for dots that could not be read
+    pub fn from_dots(dots: &Array2<Option<Dot>>) -> Self {
+        let mut bits = Array3::<i8>::from_elem((dots.nrows(), dots.ncols(), 2), ERASED);
+        for ((y, x), dot) in dots.indexed_iter() {
+            if let Some(dot) = dot {
+                let (x_bit, y_bit) = dot.bits();
+                bits[[y, x, 0]] = x_bit;
+                bits[[y, x, 1]] = y_bit;
+            }
+        }
+        BitMatrix { bits }
+    }
+
+    // Number of (rows, columns) of dots
+    pub fn shape(&self) -> (usize, usize) {
+        (self.bits.dim().0, self.bits.dim().1)
+    }
+
+    pub fn rows(&self) -> usize {
+        self.bits.dim().0
+    }
+
+    pub fn cols(&self) -> usize {
+        self.bits.dim().1
+    }
+
+    pub fn x_bit(&self, y: usize, x: usize) -> i8 {
+        self.bits[[y, x, 0]]
+    }
+
+    pub fn y_bit(&self, y: usize, x: usize) -> i8 {
+        self.bits[[y, x, 1]]
+    }
+
+    // Direction of the dot in row y and column x, None if a bit is erased
+    pub fn dot(&self, y: usize, x: usize) -> Option<Dot> {
+        Dot::from_bits(self.x_bit(y, x), self.y_bit(y, x))
+    }
+
+    pub fn set_dot(&mut self, y: usize, x: usize, dot: Option<Dot>) {
+        let (x_bit, y_bit) = dot.map_or((ERASED, ERASED), Dot::bits);
+        self.bits[[y, x, 0]] = x_bit;
+        self.bits[[y, x, 1]] = y_bit;
+    }
+
+    pub fn has_erasures(&self) -> bool {
+        self.bits.iter().any(|&b| b == ERASED)
+    }
+
+    // The dots of a rectangle of the given (rows, columns) shape whose top-left
+    // dot is at origin = (x, y). Panics if it does not fit into the matrix.
+    pub fn window(&self, origin: (usize, usize), shape: (usize, usize)) -> BitMatrix {
+        let (x, y) = origin;
+        BitMatrix {
+            bits: self.bits.slice(s![y..y + shape.0, x..x + shape.1, ..]).to_owned(),
+        }
+    }
+
+    pub fn as_array(&self) -> &Array3<i8> {
+        &self.bits
+    }
+
+    pub fn into_array(self) -> Array3<i8> {
+        self.bits
+    }
+}
+
+impl TryFrom<Array3<i8>> for BitMatrix {
+    type Error = BitMatrixError;
+
+    fn try_from(bits: Array3<i8>) -> Result<Self, Self::Error> {
+        BitMatrix::new(bits)
+    }
+}
+
+impl From<BitMatrix> for Array3<i8> {
+    fn from(matrix: BitMatrix) -> Self {
+        matrix.bits
+    }
+}
@@ -0,0 +1,198 @@
+use crate::AnotoCodec;
+use crate::lookup::table_size;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+// Properties of the codec parameters that AnotoCodecBuilder checks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodecError {
+    OrderTooSmall { order: usize },
+    MnsTooShort { length: usize, order: usize },
+    MnsNotBinary { position: usize, value: i8 },
+    MnsWindowRepeated { first: usize, repeat: usize },
+    FactorCountMismatch { sequences: usize, factors: usize },
+    SnsTooShort { sequence: usize, length: usize, order: usize },
+    SnsValueOutOfRange { sequence: usize, position: usize, value: i8, factor: i32 },
+    SnsWindowRepeated { sequence: usize, first: usize, repeat: usize },
+    SnsLengthsNotCoprime { first: usize, second: usize, gcd: usize },
+    InvalidDeltaRange { delta_range: (i32, i32), mns_length: usize },
+    FactorProductMismatch { product: i64, span: i64 },
+    OrderTooLarge { order: usize, alphabet: usize },
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CodecError::OrderTooSmall { order } => {
+                write!(f, "MNS order {} is too small, at least 2 is required", order)
+            }
+            CodecError::MnsTooShort { length, order } => {
+                write!(f, "MNS of length {} is shorter than its order {}", length, order)
+            }
+            CodecError::MnsNotBinary { position, value } => {
+                write!(f, "MNS value {} at position {} is not a bit", value, position)
+            }
+            CodecError::MnsWindowRepeated { first, repeat } => {
+                write!(f, "MNS window at position {} repeats the one at position {}", repeat, first)
+            }
+            CodecError::FactorCountMismatch { sequences, factors } => {
+                write!(f, "{} secondary sequences but {} prime factors", sequences, factors)
+            }
+            CodecError::SnsTooShort { sequence, length, order } => {
+                write!(f, "SNS {} of length {} is shorter than its order {}", sequence, length, order)
+            }
+            CodecError::SnsValueOutOfRange { sequence, position, value, factor } => {
+                write!(f, "SNS {} value {} at position {} is outside 0..{}", sequence, value, position, factor)
+            }
+            CodecError::SnsWindowRepeated { sequence, first, repeat } => {
+                write!(f, "SNS {} window at position {} repeats the one at position {}", sequence, repeat, first)
+            }
+            CodecError::SnsLengthsNotCoprime { first, second, gcd } => {
+                write!(f, "Lengths of SNS {} and SNS {} share the factor {}", first, second, gcd)
+            }
+            CodecError::InvalidDeltaRange { delta_range, mns_length } => {
+                write!(f, "Delta range {}..={} must lie within 1..{}", delta_range.0, delta_range.1, mns_length)
+            }
+            CodecError::FactorProductMismatch { product, span } => {
+                write!(f, "Prime factor product {} does not match the delta range span {}", product, span)
+            }
+            CodecError::OrderTooLarge { order, alphabet } => write!(
+                f,
+                "Lookup table for order {} over {} values exceeds {} entries",
+                order, alphabet, crate::lookup::MAX_TABLE_SIZE
+            ),
+        }
+    }
+}
+
+impl Error for CodecError {}
+
+// Builds an AnotoCodec after checking that its sequences and factors can
+// actually encode and decode positions
+#[derive(Debug, Clone, Default)]
+pub struct AnotoCodecBuilder {
+    mns: Vec<i8>,
+    mns_order: usize,
+    sns: Vec<Vec<i8>>,
+    pfactors: Vec<i32>,
+    delta_range: (i32, i32),
+}
+
+impl AnotoCodecBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mns(mut self, mns: Vec<i8>, mns_order: usize) -> Self {
+        self.mns = mns;
+        self.mns_order = mns_order;
+        self
+    }
+
+    pub fn sns(mut self, sns: Vec<Vec<i8>>) -> Self {
+        self.sns = sns;
+        self
+    }
+
+    pub fn pfactors(mut self, pfactors: Vec<i32>) -> Self {
+        self.pfactors = pfactors;
+        self
+    }
+
+    pub fn delta_range(mut self, delta_range: (i32, i32)) -> Self {
+        self.delta_range = delta_range;
+        self
+    }
+
+    pub fn build(self) -> Result<AnotoCodec, CodecError> {
+        self.validate()?;
+        Ok(AnotoCodec::new(
+            self.mns,
+            self.mns_order,
+            self.sns,
+            self.pfactors,
+            self.delta_range,
+        ))
+    }
+
+    // Check every property in turn and report the first one violated
+    pub fn validate(&self) -> Result<(), CodecError> {
+        let order = self.mns_order;
+        if order < 2 {
+            return Err(CodecError::OrderTooSmall { order });
+        }
+
+        if self.mns.len() < order {
+            return Err(CodecError::MnsTooShort { length: self.mns.len(), order });
+        }
+        if let Some((position, &value)) = self.mns.iter().enumerate().find(|&(_, &v)| v != 0 && v != 1) {
+            return Err(CodecError::MnsNotBinary { position, value });
+        }
+        if let Some((first, repeat)) = find_repeated_window(&self.mns, order) {
+            return Err(CodecError::MnsWindowRepeated { first, repeat });
+        }
+        table_size(&self.mns, order)?;
+
+        if self.sns.len() != self.pfactors.len() {
+            return Err(CodecError::FactorCountMismatch {
+                sequences: self.sns.len(),
+                factors: self.pfactors.len(),
+            });
+        }
+        for (sequence, (sns, &factor)) in self.sns.iter().zip(self.pfactors.iter()).enumerate() {
+            if sns.len() < order - 1 {
+                return Err(CodecError::SnsTooShort { sequence, length: sns.len(), order: order - 1 });
+            }
+            if let Some((position, &value)) = sns.iter().enumerate().find(|&(_, &v)| v < 0 || v as i32 >= factor) {
+                return Err(CodecError::SnsValueOutOfRange { sequence, position, value, factor });
+            }
+            if let Some((first, repeat)) = find_repeated_window(sns, order - 1) {
+                return Err(CodecError::SnsWindowRepeated { sequence, first, repeat });
+            }
+            table_size(sns, order - 1)?;
+        }
+
+        for i in 0..self.sns.len() {
+            for j in (i + 1)..self.sns.len() {
+                let gcd = gcd(self.sns[i].len(), self.sns[j].len());
+                if gcd != 1 {
+                    return Err(CodecError::SnsLengthsNotCoprime { first: i, second: j, gcd });
+                }
+            }
+        }
+
+        let (lo, hi) = self.delta_range;
+        if lo < 1 || hi < lo || hi as usize >= self.mns.len() {
+            return Err(CodecError::InvalidDeltaRange {
+                delta_range: self.delta_range,
+                mns_length: self.mns.len(),
+            });
+        }
+
+        let product: i64 = self.pfactors.iter().map(|&f| f as i64).product();
+        let span = (hi - lo + 1) as i64;
+        if product != span {
+            return Err(CodecError::FactorProductMismatch { product, span });
+        }
+
+        Ok(())
+    }
+}
+
+// Positions of the first window of the cyclic sequence that occurs twice
+fn find_repeated_window(seq: &[i8], order: usize) -> Option<(usize, usize)> {
+    let mut seen: HashMap<Vec<i8>, usize> = HashMap::new();
+    for pos in 0..seq.len() {
+        let window: Vec<i8> = (0..order).map(|i| seq[(pos + i) % seq.len()]).collect();
+        if let Some(&first) = seen.get(&window) {
+            return Some((first, pos));
+        }
+        seen.insert(window, pos);
+    }
+    None
+}
+
+pub(crate) fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
@@ -0,0 +1,110 @@
+use crate::bitmatrix::Dot;
+use serde::{Deserialize, Serialize};
+
+pub(crate) const MM_PER_INCH: f64 = 25.4;
+const POINTS_PER_INCH: f64 = 72.0;
+
+// Length units for paper coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Unit {
+    Millimetre,
+    Inch,
+    Point,
+}
+
+impl Unit {
+    // Length of one millimetre in this unit
+    pub fn per_mm(self) -> f64 {
+        match self {
+            Unit::Millimetre => 1.0,
+            Unit::Inch => 1.0 / MM_PER_INCH,
+            Unit::Point => POINTS_PER_INCH / MM_PER_INCH,
+        }
+    }
+}
+
+// Placement of the dot grid on paper. Paper coordinates are in millimetres
+// with x to the right and y downwards, like the rows of a bit-matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GridGeometry {
+    // Distance between neighbouring grid points
+    pub pitch_mm: f64,
+    // Offset of a dot from its grid point as a fraction of the pitch
+    pub displacement: f64,
+    // Paper coordinates of the grid point at position (0, 0)
+    pub origin_mm: (f64, f64),
+}
+
+impl Default for GridGeometry {
+    // The nominal Anoto grid: 0.3 mm pitch, dots displaced by 1/6 of it
+    fn default() -> Self {
+        GridGeometry {
+            pitch_mm: 0.3,
+            displacement: 1.0 / 6.0,
+            origin_mm: (0.0, 0.0),
+        }
+    }
+}
+
+impl GridGeometry {
+    pub fn new(pitch_mm: f64, displacement: f64, origin_mm: (f64, f64)) -> Self {
+        GridGeometry {
+            pitch_mm,
+            displacement,
+            origin_mm,
+        }
+    }
+
+    pub fn with_origin(self, origin_mm: (f64, f64)) -> Self {
+        GridGeometry { origin_mm, ..self }
+    }
+
+    // Paper coordinates of the grid point of a position
+    pub fn grid_point(&self, position: (i32, i32)) -> (f64, f64) {
+        (
+            self.origin_mm.0 + position.0 as f64 * self.pitch_mm,
+            self.origin_mm.1 + position.1 as f64 * self.pitch_mm,
+        )
+    }
+
+    // Paper coordinates of the centre of a dot printed at a position
+    pub fn dot_centre(&self, position: (i32, i32), dot: Dot) -> (f64, f64) {
+        let (gx, gy) = self.grid_point(position);
+        let (dx, dy) = dot.displacement();
+        let offset = self.displacement * self.pitch_mm;
+        (gx + dx as f64 * offset, gy + dy as f64 * offset)
+    }
+
+    // Position of the grid point nearest to paper coordinates
+    pub fn position_at(&self, mm: (f64, f64)) -> (i32, i32) {
+        (
+            ((mm.0 - self.origin_mm.0) / self.pitch_mm).round() as i32,
+            ((mm.1 - self.origin_mm.1) / self.pitch_mm).round() as i32,
+        )
+    }
+
+    // Like grid_point, in the given unit
+    pub fn grid_point_in(&self, position: (i32, i32), unit: Unit) -> (f64, f64) {
+        let (x, y) = self.grid_point(position);
+        (x * unit.per_mm(), y * unit.per_mm())
+    }
+
+    // Like dot_centre, in the given unit
+    pub fn dot_centre_in(&self, position: (i32, i32), dot: Dot, unit: Unit) -> (f64, f64) {
+        let (x, y) = self.dot_centre(position, dot);
+        (x * unit.per_mm(), y * unit.per_mm())
+    }
+
+    // Like position_at, for coordinates in the given unit
+    pub fn position_at_in(&self, coords: (f64, f64), unit: Unit) -> (i32, i32) {
+        self.position_at((coords.0 / unit.per_mm(), coords.1 / unit.per_mm()))
+    }
+}
+
+// Length with up to four decimals and no trailing zeros, as written into
+// vector documents
+pub(crate) fn format_length(value: f64) -> String {
+    let s = format!("{:.4}", value);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" { "0".to_string() } else { s.to_string() }
+}
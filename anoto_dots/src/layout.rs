@@ -0,0 +1,181 @@
+use crate::AnotoCodec;
+use crate::address::DotAddress;
+use crate::bitmatrix::BitMatrix;
+use crate::geometry::{GridGeometry, MM_PER_INCH};
+use serde::{Deserialize, Serialize};
+
+// Tolerance for dot counts that are whole up to rounding errors
+const FIT_EPSILON: f64 = 1e-9;
+
+// Paper sizes in portrait orientation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PaperSize {
+    A3,
+    A4,
+    A5,
+    Letter,
+    Legal,
+    Custom { width_mm: f64, height_mm: f64 },
+}
+
+impl PaperSize {
+    // (width, height) in millimetres
+    pub fn dimensions_mm(self) -> (f64, f64) {
+        match self {
+            PaperSize::A3 => (297.0, 420.0),
+            PaperSize::A4 => (210.0, 297.0),
+            PaperSize::A5 => (148.0, 210.0),
+            PaperSize::Letter => (215.9, 279.4),
+            PaperSize::Legal => (215.9, 355.6),
+            PaperSize::Custom { width_mm, height_mm } => (width_mm, height_mm),
+        }
+    }
+
+    // The same paper turned sideways
+    pub fn landscape(self) -> PaperSize {
+        let (width_mm, height_mm) = self.dimensions_mm();
+        PaperSize::Custom {
+            width_mm: height_mm,
+            height_mm: width_mm,
+        }
+    }
+}
+
+// Unprinted border of a page in millimetres
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Margins {
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+    pub left: f64,
+}
+
+impl Margins {
+    pub fn uniform(mm: f64) -> Self {
+        Margins {
+            top: mm,
+            right: mm,
+            bottom: mm,
+            left: mm,
+        }
+    }
+}
+
+// Rectangle of the global pattern space, in dots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PatternRect {
+    pub origin: DotAddress,
+    pub width: u64,
+    pub height: u64,
+}
+
+impl PatternRect {
+    pub fn contains(&self, address: DotAddress) -> bool {
+        address.x >= self.origin.x
+            && address.y >= self.origin.y
+            && address.x - self.origin.x < self.width
+            && address.y - self.origin.y < self.height
+    }
+
+    pub fn overlaps(&self, other: &PatternRect) -> bool {
+        self.origin.x < other.origin.x + other.width
+            && other.origin.x < self.origin.x + self.width
+            && self.origin.y < other.origin.y + other.height
+            && other.origin.y < self.origin.y + self.height
+    }
+
+    // (rows, columns) of dots, as taken by encode_region
+    pub fn shape(&self) -> (usize, usize) {
+        (self.height as usize, self.width as usize)
+    }
+}
+
+// How a page of pattern is printed: the paper, its margins, the printer
+// resolution and the dot grid. Every dot sits in the centre of a pitch-sized
+// cell, and the cells fill the area inside the margins from the top left.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PageLayout {
+    pub paper: PaperSize,
+    pub margins: Margins,
+    pub dpi: u32,
+    pub grid: GridGeometry,
+}
+
+impl PageLayout {
+    pub fn new(paper: PaperSize, margins: Margins, dpi: u32, grid: GridGeometry) -> Self {
+        PageLayout {
+            paper,
+            margins,
+            dpi,
+            grid,
+        }
+    }
+
+    // Presets with 10 mm margins, 600 DPI and the nominal grid
+    pub fn a4() -> Self {
+        Self::preset(PaperSize::A4)
+    }
+
+    pub fn a5() -> Self {
+        Self::preset(PaperSize::A5)
+    }
+
+    pub fn letter() -> Self {
+        Self::preset(PaperSize::Letter)
+    }
+
+    fn preset(paper: PaperSize) -> Self {
+        PageLayout::new(paper, Margins::uniform(10.0), 600, GridGeometry::default())
+    }
+
+    // (width, height) in millimetres of the area inside the margins
+    pub fn printable_mm(&self) -> (f64, f64) {
+        let (width, height) = self.paper.dimensions_mm();
+        (
+            (width - self.margins.left - self.margins.right).max(0.0),
+            (height - self.margins.top - self.margins.bottom).max(0.0),
+        )
+    }
+
+    // (rows, columns) of dots fitting inside the margins, the shape to pass
+    // to encode_bitmatrix or encode_region
+    pub fn shape(&self) -> (usize, usize) {
+        let (width, height) = self.printable_mm();
+        let fit = |mm: f64| (mm / self.grid.pitch_mm + FIT_EPSILON).floor() as usize;
+        (fit(height), fit(width))
+    }
+
+    // Grid geometry placing the first dot of the page in paper coordinates
+    pub fn page_grid(&self) -> GridGeometry {
+        let half = self.grid.pitch_mm / 2.0;
+        self.grid.with_origin((self.margins.left + half, self.margins.top + half))
+    }
+
+    // (width, height) of the page in printer pixels
+    pub fn pixel_size(&self) -> (u32, u32) {
+        let (width, height) = self.paper.dimensions_mm();
+        let px = |mm: f64| (mm / MM_PER_INCH * self.dpi as f64).round() as u32;
+        (px(width), px(height))
+    }
+
+    // Grid pitch in printer pixels, usually not a whole number
+    pub fn pitch_px(&self) -> f64 {
+        self.grid.pitch_mm / MM_PER_INCH * self.dpi as f64
+    }
+
+    // Pattern space used by a page whose top-left dot is at origin
+    pub fn pattern_rect(&self, origin: DotAddress) -> PatternRect {
+        let (rows, cols) = self.shape();
+        PatternRect {
+            origin,
+            width: cols as u64,
+            height: rows as u64,
+        }
+    }
+
+    // Dots of a page whose top-left dot is at origin, None if the page does
+    // not fit into one section
+    pub fn encode_page(&self, codec: &AnotoCodec, origin: DotAddress) -> Option<BitMatrix> {
+        codec.encode_address_region(origin, self.shape())
+    }
+}
@@ -1,28 +1,120 @@
-use ndarray::{Array2, Array3, Axis, s};
+use ndarray::{Array2, Array3, ArrayView2, ArrayView3, Axis, s};
+use serde::Serialize;
 use std::error::Error;
 use std::fmt;
 
+pub mod address;
+pub mod allocator;
+pub mod bitmatrix;
+pub mod builder;
+pub mod geometry;
+pub mod layout;
+pub mod lookup;
+pub mod overlay;
+pub mod pdf;
+pub mod pdf_import;
 pub mod persist;
+pub mod position_map;
+pub mod raster;
+pub mod registry;
+pub mod report;
+pub mod sequences;
+pub mod spec;
+pub mod svg;
 
-// Custom error type for decoding errors
-#[derive(Debug)]
-pub struct DecodingError {
-    message: String,
+use address::DotAddress;
+use bitmatrix::BitMatrix;
+use builder::AnotoCodecBuilder;
+use geometry::GridGeometry;
+use lookup::WindowIndex;
+use position_map::PositionMap;
+use report::{AxisReport, DecodeReport};
+use spec::CodecSpec;
+
+// Pattern axis a decoding step worked on. The x-bits of a window are read
+// column by column and the y-bits row by row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Coordinate {
+    X,
+    Y,
+}
+
+impl fmt::Display for Coordinate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Coordinate::X => write!(f, "x"),
+            Coordinate::Y => write!(f, "y"),
+        }
+    }
+}
+
+// Reasons a window fails to decode. Rows count along the bit plane of the
+// axis, so for x they are the columns of the window.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum DecodingError {
+    ShapeMismatch { shape: (usize, usize), order: usize },
+    MnsMiss { axis: Coordinate, row: usize },
+    DeltaOutOfRange { axis: Coordinate, row: usize, value: i32 },
+    SnsMiss { axis: Coordinate, sequence: usize },
+    CrtFailure { remainders: Vec<i64>, moduli: Vec<i64> },
+    // Input that matches more than one location, e.g. due to erased dots
+    Ambiguous,
+    TooManyCorrections { corrections: usize, max_corrections: usize },
+    CorrectionAmbiguous,
+    NoConsistentPosition,
+    NoRotation,
+    RotationAmbiguous,
+    PositionMismatch { window: (usize, usize), decoded: (i32, i32) },
+    SectionMismatch { window: (usize, usize), decoded: (i32, i32), expected: (i32, i32) },
 }
 
 impl fmt::Display for DecodingError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Decoding error: {}", self.message)
+        write!(f, "Decoding error: ")?;
+        match self {
+            DecodingError::ShapeMismatch { shape, order } => {
+                write!(f, "Window of shape ({}, {}) is smaller than the MNS order {}", shape.0, shape.1, order)
+            }
+            DecodingError::MnsMiss { axis, row } => {
+                write!(f, "Failed to find partial sequence in MNS ({}, row {})", axis, row)
+            }
+            DecodingError::DeltaOutOfRange { axis, row, value } => {
+                write!(f, "Delta value out of range: {} ({}, row {})", value, axis, row)
+            }
+            DecodingError::SnsMiss { axis, sequence } => {
+                write!(f, "Failed to find coefficients in SNS ({}, sequence {})", axis, sequence)
+            }
+            DecodingError::CrtFailure { remainders, moduli } if remainders.len() != moduli.len() => {
+                write!(f, "Remainders and moduli length mismatch ({} and {})", remainders.len(), moduli.len())
+            }
+            DecodingError::CrtFailure { remainders, moduli } => {
+                write!(f, "Modular inverse does not exist (remainders {:?}, moduli {:?})", remainders, moduli)
+            }
+            DecodingError::Ambiguous => write!(f, "Erased dots leave the match ambiguous"),
+            DecodingError::TooManyCorrections { corrections, max_corrections } => {
+                write!(f, "Too many dots to correct: {} of at most {}", corrections, max_corrections)
+            }
+            DecodingError::CorrectionAmbiguous => write!(f, "Corrected position is ambiguous"),
+            DecodingError::NoConsistentPosition => write!(f, "No consistent position found"),
+            DecodingError::NoRotation => write!(f, "No rotation yields a valid pattern"),
+            DecodingError::RotationAmbiguous => write!(f, "Rotation is ambiguous, use a larger window"),
+            DecodingError::PositionMismatch { window, decoded } => {
+                write!(f, "Window at ({}, {}) decoded to position ({}, {})", window.0, window.1, decoded.0, decoded.1)
+            }
+            DecodingError::SectionMismatch { window, decoded, expected } => write!(
+                f,
+                "Window at ({}, {}) decoded to section ({}, {}), expected ({}, {})",
+                window.0, window.1, decoded.0, decoded.1, expected.0, expected.1
+            ),
+        }
     }
 }
 
 impl Error for DecodingError {}
 
 impl DecodingError {
-    pub fn new(message: &str) -> Self {
-        DecodingError {
-            message: message.to_string(),
-        }
+    pub fn is_ambiguous(&self) -> bool {
+        matches!(self, DecodingError::Ambiguous)
     }
 }
 
@@ -71,27 +163,34 @@ impl CRT {
 
     fn solve(&self, remainders: &[i64]) -> Result<i64, DecodingError> {
         if remainders.len() != self.moduli.len() {
-            return Err(DecodingError::new("Remainders and moduli length mismatch"));
+            return Err(DecodingError::CrtFailure {
+                remainders: remainders.to_vec(),
+                moduli: self.moduli.clone(),
+            });
         }
 
         let mut result = 0i64;
         let product: i64 = self.moduli.iter().product();
 
-        for (_i, (&remainder, &modulus)) in remainders.iter().zip(self.moduli.iter()).enumerate() {
+        for (&remainder, &modulus) in remainders.iter().zip(self.moduli.iter()) {
             let partial_product = product / modulus;
-            let inverse = self.mod_inverse(partial_product, modulus)?;
+            let inverse = self.mod_inverse(partial_product, modulus)
+                .ok_or_else(|| DecodingError::CrtFailure {
+                    remainders: remainders.to_vec(),
+                    moduli: self.moduli.clone(),
+                })?;
             result = (result + remainder * partial_product * inverse) % product;
         }
 
         Ok(result)
     }
 
-    fn mod_inverse(&self, a: i64, m: i64) -> Result<i64, DecodingError> {
+    fn mod_inverse(&self, a: i64, m: i64) -> Option<i64> {
         let (gcd, x, _) = self.extended_gcd(a, m);
         if gcd != 1 {
-            return Err(DecodingError::new("Modular inverse does not exist"));
+            return None;
         }
-        Ok((x % m + m) % m)
+        Some((x % m + m) % m)
     }
 
     fn extended_gcd(&self, a: i64, b: i64) -> (i64, i64, i64) {
@@ -105,22 +204,43 @@ impl CRT {
     }
 }
 
-// Main Anoto codec implementation
+// Marks a dot that could not be read (hidden by ink, glare or the nib). Erased
+// values may be used for either bit of a window and match any bit value.
+pub const ERASED: i8 = -1;
+
+// Upper bound on position combinations tried by the error-correcting decoder
+// and on MNS location combinations tried for windows with erased dots
+const MAX_CORRECTION_CANDIDATES: usize = 256;
+
+// Rows of a window matching more MNS locations than this are treated as unknown
+const MAX_ROW_CANDIDATES: usize = 4;
+
+// How many more dots the runner-up position of the error-correcting decoder
+// must disagree with than the chosen one
+const MIN_CORRECTION_MARGIN: usize = 2;
+
+// Main Anoto codec implementation. All lookup tables are built once in new
+// and only read while decoding, so the codec is Send + Sync and a single
+// instance can be shared between threads.
 pub struct AnotoCodec {
     mns: Vec<i8>,
     mns_length: usize,
-    mns_cyclic: Vec<i8>,
+    mns_index: WindowIndex,
     mns_order: usize,
     _sns_order: usize,
-    _sns: Vec<Vec<i8>>,
+    sns: Vec<Vec<i8>>,
     sns_lengths: Vec<usize>,
-    sns_cyclic: Vec<Vec<i8>>,
+    sns_index: Vec<WindowIndex>,
+    sns_prefix_sums: Vec<Vec<i64>>,
     num_basis: NumberBasis,
     crt: CRT,
     delta_range: (i32, i32),
 }
 
 impl AnotoCodec {
+    // Build a codec without checking its parameters. Panics if a lookup
+    // table would exceed lookup::MAX_TABLE_SIZE, see AnotoCodecBuilder for a
+    // validated construction.
     pub fn new(
         mns: Vec<i8>,
         mns_order: usize,
@@ -129,67 +249,136 @@ impl AnotoCodec {
         delta_range: (i32, i32),
     ) -> Self {
         let mns_length = mns.len();
-        let mns_cyclic = make_cyclic(&mns, mns_order);
+        let mns_index = WindowIndex::new(&mns, mns_order).expect("MNS lookup table fits");
         let sns_order = mns_order - 1;
         let sns_lengths: Vec<usize> = sns.iter().map(|s| s.len()).collect();
-        let sns_cyclic: Vec<Vec<i8>> = sns.iter().map(|s| make_cyclic(s, sns_order)).collect();
+        let sns_index: Vec<WindowIndex> = sns.iter()
+            .map(|s| WindowIndex::new(s, sns_order).expect("SNS lookup table fits"))
+            .collect();
+        let sns_prefix_sums: Vec<Vec<i64>> = sns.iter().map(|s| prefix_sums(s)).collect();
         let num_basis = NumberBasis::new(pfactors.into_iter().map(|x| x as i64).collect());
         let crt = CRT::new(sns_lengths.iter().map(|&l| l as i64).collect());
 
         AnotoCodec {
             mns,
             mns_length,
-            mns_cyclic,
+            mns_index,
             mns_order,
             _sns_order: sns_order,
-            _sns: sns,
+            sns,
             sns_lengths,
-            sns_cyclic,
+            sns_index,
+            sns_prefix_sums,
             num_basis,
             crt,
             delta_range,
         }
     }
 
-    pub fn encode_bitmatrix(&self, shape: (usize, usize), section: (i32, i32)) -> Array3<i8> {
-        // Find nearest multiples of MNS length for ease of generation
-        let mshape = (
-            (self.mns_length as f64 * (shape.0 as f64 / self.mns_length as f64).ceil()) as usize,
-            (self.mns_length as f64 * (shape.1 as f64 / self.mns_length as f64).ceil()) as usize,
-        );
+    // Start a validated codec construction, see AnotoCodecBuilder
+    pub fn builder() -> AnotoCodecBuilder {
+        AnotoCodecBuilder::new()
+    }
+
+    // Export the sequences and factors of this codec under the given name
+    pub fn to_spec(&self, name: &str) -> CodecSpec {
+        CodecSpec {
+            name: name.to_string(),
+            mns: self.mns.clone(),
+            mns_order: self.mns_order,
+            sns: self.sns.clone(),
+            pfactors: self.num_basis.factors.iter().map(|&f| f as i32).collect(),
+            delta_range: self.delta_range,
+        }
+    }
+
+    // Number of distinct positions along each axis of a section, after which
+    // the deltas repeat
+    pub fn section_size(&self) -> u64 {
+        self.sns_lengths.iter().map(|&l| l as u64).product()
+    }
+
+    // Number of distinct sections along each axis
+    pub fn section_count(&self) -> usize {
+        self.mns_length
+    }
+
+    // Pack a section and a position within it into a global address. Both
+    // are taken modulo their range.
+    pub fn dot_address(&self, section: (i32, i32), position: (i32, i32)) -> DotAddress {
+        let size = self.section_size() as i64;
+        let pack = |s: i32, p: i32| {
+            (s.rem_euclid(self.mns_length as i32) as i64 * size + (p as i64).rem_euclid(size)) as u64
+        };
+        DotAddress::new(pack(section.0, position.0), pack(section.1, position.1))
+    }
+
+    // Split a global address into its section and the position within it
+    pub fn address_parts(&self, address: DotAddress) -> ((i32, i32), (i32, i32)) {
+        let size = self.section_size();
+        let split = |a: u64| ((a / size) as i32, (a % size) as i32);
+        let (sx, px) = split(address.x);
+        let (sy, py) = split(address.y);
+        ((sx, sy), (px, py))
+    }
 
-        let mut m = Array3::<i8>::zeros((mshape.0, mshape.1, 2));
+    // Encode the dots of a rectangle of the given shape whose top-left dot is
+    // at a global address. None if the rectangle leaves the section of its
+    // top-left dot along either axis, or the address lies beyond the last one.
+    pub fn encode_address_region(&self, origin: DotAddress, shape: (usize, usize)) -> Option<BitMatrix> {
+        let size = self.section_size();
+        let within = |a: u64, len: usize| {
+            a / size < self.mns_length as u64 && (a % size) + len as u64 <= size
+        };
+        if !within(origin.x, shape.1) || !within(origin.y, shape.0) {
+            return None;
+        }
+
+        let (section, position) = self.address_parts(origin);
+        Some(self.encode_region((position.0 as usize, position.1 as usize), shape, section))
+    }
+
+    pub fn encode_bitmatrix(&self, shape: (usize, usize), section: (i32, i32)) -> BitMatrix {
+        self.encode_region((0, 0), shape, section)
+    }
+
+    // Encode the dots of a rectangle of the given shape whose top-left dot is
+    // at the absolute position origin = (x, y) within a section, without
+    // generating any of the pattern before it
+    pub fn encode_region(&self, origin: (usize, usize), shape: (usize, usize), section: (i32, i32)) -> BitMatrix {
+        let mut m = Array3::<i8>::zeros((shape.0, shape.1, 2));
 
-        // x-direction
-        let mut roll = section.0 % self.mns_length as i32;
-        let _ytiles = mshape.0 / self.mns_length;
+        // x-direction, starting from the roll accumulated up to the first column
+        let mut roll = self.integrate_roll(origin.0 as i32, section.0.rem_euclid(self.mns_length as i32));
 
-        for x in 0..mshape.1 {
-            roll = self.next_roll(x as i32, roll);
-            let rolled_mns = rotate_vec(&self.mns, -(roll as isize));
-            let tiled: Vec<i8> = rolled_mns.iter().cycle().take(mshape.0).cloned().collect();
+        for x in 0..shape.1 {
+            if x > 0 {
+                roll = self.next_roll((origin.0 + x) as i32, roll);
+            }
+            let rolled_mns = rotate_vec(&self.mns, -((roll as usize + origin.1) as isize));
+            let tiled: Vec<i8> = rolled_mns.iter().cycle().take(shape.0).cloned().collect();
 
-            for y in 0..mshape.0 {
+            for y in 0..shape.0 {
                 m[[y, x, 0]] = tiled[y];
             }
         }
 
         // y-direction
-        let mut roll = section.1 % self.mns_length as i32;
-        let _xtiles = mshape.1 / self.mns_length;
+        let mut roll = self.integrate_roll(origin.1 as i32, section.1.rem_euclid(self.mns_length as i32));
 
-        for y in 0..mshape.0 {
-            roll = self.next_roll(y as i32, roll);
-            let rolled_mns = rotate_vec(&self.mns, -(roll as isize));
-            let tiled: Vec<i8> = rolled_mns.iter().cycle().take(mshape.1).cloned().collect();
+        for y in 0..shape.0 {
+            if y > 0 {
+                roll = self.next_roll((origin.1 + y) as i32, roll);
+            }
+            let rolled_mns = rotate_vec(&self.mns, -((roll as usize + origin.0) as isize));
+            let tiled: Vec<i8> = rolled_mns.iter().cycle().take(shape.1).cloned().collect();
 
-            for x in 0..mshape.1 {
+            for x in 0..shape.1 {
                 m[[y, x, 1]] = tiled[x];
             }
         }
 
-        // Return the requested shape
-        m.slice(s![0..shape.0, 0..shape.1, ..]).to_owned()
+        BitMatrix::new(m).expect("MNS values are bits")
     }
 
     fn next_roll(&self, pos: i32, prev_roll: i32) -> i32 {
@@ -200,97 +389,581 @@ impl AnotoCodec {
     }
 
     fn delta(&self, pos: i32) -> i32 {
-        let rs: Vec<usize> = self.sns_lengths.iter()
-            .map(|&len| (pos as usize) % len)
-            .collect();
-
-        let coeffs: Vec<i8> = rs.iter().zip(self.sns_cyclic.iter())
-            .map(|(&r, s)| s[r])
+        let coeffs: Vec<i8> = self.sns.iter()
+            .map(|s| s[pos.rem_euclid(s.len() as i32) as usize])
             .collect();
 
         (self.num_basis.reconstruct(&coeffs) + self.delta_range.0 as i64) as i32
     }
 
-    pub fn decode_position(&self, bits: &Array3<i8>) -> Result<(i32, i32), DecodingError> {
-        if bits.dim().2 != 2 {
-            return Err(DecodingError::new("Expected (M,N,2) matrix"));
-        }
-
-        let sub_bits = bits.slice(s![0..self.mns_order, 0..self.mns_order, ..]);
+    pub fn decode_position(&self, bits: &BitMatrix) -> Result<(i32, i32), DecodingError> {
+        self.check_shape(bits)?;
+        let sub_bits = self.decoding_window(bits.as_array());
         
         // Decode x (transpose for x-direction)
         let x_bits = sub_bits.slice(s![.., .., 0]).t().to_owned();
-        let x = self.decode_position_along_direction(&x_bits)?;
+        let x = self.decode_position_along_direction(Coordinate::X, &x_bits, None)?;
         
         // Decode y
         let y_bits = sub_bits.slice(s![.., .., 1]).to_owned();
-        let y = self.decode_position_along_direction(&y_bits)?;
+        let y = self.decode_position_along_direction(Coordinate::Y, &y_bits, None)?;
 
         Ok((x, y))
     }
 
-    fn decode_position_along_direction(&self, bits: &Array2<i8>) -> Result<i32, DecodingError> {
-        let mut locs = Vec::new();
+    // Decode position and section like decode_position and decode_section, but
+    // also return the intermediate values of every step and where it failed
+    pub fn decode_with_report(&self, bits: &BitMatrix) -> DecodeReport {
+        let mut report = DecodeReport {
+            shape: bits.shape(),
+            x: AxisReport::default(),
+            y: AxisReport::default(),
+            position: None,
+            section: None,
+            error: None,
+        };
+
+        let result = self.check_shape(bits).and_then(|_| {
+            let sub_bits = self.decoding_window(bits.as_array());
+            let x_bits = sub_bits.slice(s![.., .., 0]).t().to_owned();
+            let x = self.decode_position_along_direction(Coordinate::X, &x_bits, Some(&mut report.x))?;
+            let y_bits = sub_bits.slice(s![.., .., 1]).to_owned();
+            let y = self.decode_position_along_direction(Coordinate::Y, &y_bits, Some(&mut report.y))?;
+            report.position = Some((x, y));
+
+            report.section = Some(self.decode_section(bits, (x, y))?);
+            Ok(())
+        });
+
+        report.error = result.err();
+        report
+    }
+
+    fn check_shape(&self, bits: &BitMatrix) -> Result<(), DecodingError> {
+        if bits.rows() < self.mns_order || bits.cols() < self.mns_order {
+            return Err(DecodingError::ShapeMismatch { shape: bits.shape(), order: self.mns_order });
+        }
+        Ok(())
+    }
+
+    // The part of a window used for exact decoding: the top-left mns_order block,
+    // or the whole window if it has erased dots the extra rows may resolve
+    fn decoding_window<'a>(&self, bits: &'a Array3<i8>) -> ArrayView3<'a, i8> {
+        if bits.iter().any(|&b| b == ERASED) {
+            bits.view()
+        } else {
+            bits.slice(s![0..self.mns_order, 0..self.mns_order, ..])
+        }
+    }
+
+    // Decode one axis, recording the intermediate values in trace if given
+    fn decode_position_along_direction(
+        &self,
+        axis: Coordinate,
+        bits: &Array2<i8>,
+        mut trace: Option<&mut AxisReport>,
+    ) -> Result<i32, DecodingError> {
+        // Erased dots may let a row match several MNS locations. Rows with a
+        // few candidates are tried in every combination, rows with many are
+        // left unknown.
+        let mut row_locs = Vec::new();
         
-        for row in bits.axis_iter(Axis(0)) {
+        for (r, row) in bits.axis_iter(Axis(0)).enumerate() {
             let row_vec: Vec<i8> = row.to_vec();
-            match find_subsequence(&self.mns_cyclic, &row_vec) {
-                Some(pos) => locs.push(pos as i32),
-                None => return Err(DecodingError::new("Failed to find partial sequence in MNS")),
+            let matches = self.mns_index.find_all(&row_vec);
+            if let Some(t) = trace.as_deref_mut() {
+                t.locations.push(matches.clone());
+            }
+            match matches.len() {
+                0 => return Err(DecodingError::MnsMiss { axis, row: r }),
+                n if n > MAX_ROW_CANDIDATES => row_locs.push(vec![None]),
+                _ => row_locs.push(matches.into_iter().map(|pos| Some(pos as i32)).collect()),
             }
         }
 
-        // Compute differences
-        let mut deltae = Vec::new();
-        for i in 1..locs.len() {
-            let diff = (locs[i] - locs[i-1] + self.mns_length as i32) % self.mns_length as i32;
-            if diff < self.delta_range.0 || diff > self.delta_range.1 {
-                return Err(DecodingError::new("Delta value out of range"));
+        let combinations = cartesian_product(&row_locs, MAX_CORRECTION_CANDIDATES)
+            .ok_or(DecodingError::Ambiguous)?;
+
+        // Only one combination of locations may be consistent with the SNS.
+        // The trace keeps the steps of the first consistent combination, or of
+        // the first one tried if none is.
+        let mut found = None;
+        let mut last_err = None;
+        for (i, locs) in combinations.iter().enumerate() {
+            let mut attempt = trace.as_ref().map(|_| AxisReport::default());
+            let result = self.decode_locations(axis, locs, attempt.as_mut());
+            if let (Some(t), Some(a)) = (trace.as_deref_mut(), attempt)
+                && (i == 0 || (result.is_ok() && found.is_none()))
+            {
+                t.deltas = a.deltas;
+                t.coefficients = a.coefficients;
+                t.sns_positions = a.sns_positions;
+            }
+
+            match result {
+                Ok(pos) if found.is_some_and(|f| f != pos) => {
+                    return Err(DecodingError::Ambiguous);
+                }
+                Ok(pos) => found = Some(pos),
+                Err(e) if e.is_ambiguous() => return Err(e),
+                Err(e) => last_err = Some(e),
             }
-            deltae.push(diff - self.delta_range.0);
         }
 
-        // Project to coefficients
-        let deltae_i64: Vec<i64> = deltae.iter().map(|&x| x as i64).collect();
-        let coeffs = self.num_basis.project(&deltae_i64);
-        
+        if let Some(t) = trace {
+            t.position = found;
+        }
+        match (found, last_err) {
+            (Some(pos), _) => Ok(pos),
+            (None, Some(e)) => Err(e),
+            (None, None) => Err(DecodingError::NoConsistentPosition),
+        }
+    }
+
+    // Decode the position from per-row MNS locations, where unknown locations
+    // turn the adjacent deltas into wildcards for the SNS lookup
+    fn decode_locations(&self, axis: Coordinate, locs: &[Option<i32>], mut trace: Option<&mut AxisReport>) -> Result<i32, DecodingError> {
+        let coeffs = self.location_coefficients(axis, locs, trace.as_deref_mut())?;
+
         // Find positions in secondary sequences
         let mut ps = Vec::new();
-        for (i, sns_seq) in self.sns_cyclic.iter().enumerate() {
+        let mut missed = None;
+        for (i, sns_index) in self.sns_index.iter().enumerate() {
             let coeff_seq: Vec<i8> = coeffs.iter().map(|c| c[i]).collect();
-            match find_subsequence(sns_seq, &coeff_seq) {
-                Some(pos) => ps.push(pos as i64),
-                None => return Err(DecodingError::new("Failed to find coefficients in SNS")),
+            match sns_index.find_unique(&coeff_seq) {
+                Ok(Some(pos)) => ps.push(pos as i64),
+                Ok(None) => missed = Some(DecodingError::SnsMiss { axis, sequence: i }),
+                Err(e) => missed = Some(e),
+            }
+            if missed.is_some() {
+                break;
             }
         }
+        if let Some(t) = trace {
+            t.sns_positions = ps.clone();
+        }
+        if let Some(e) = missed {
+            return Err(e);
+        }
 
         self.crt.solve(&ps).map(|x| x as i32)
     }
 
-    pub fn decode_section(&self, bits: &Array3<i8>, pos: (i32, i32)) -> Result<(i32, i32), DecodingError> {
-        let px_seq = bits.slice(s![0..self.mns_order, 0, 0]).to_vec();
-        let py_seq = bits.slice(s![0, 0..self.mns_order, 1]).to_vec();
+    // Every position consistent with per-row MNS locations, some of which may
+    // be unknown. CorrectionAmbiguous if there are too many to list.
+    fn consistent_positions(&self, axis: Coordinate, locs: &[Option<i32>]) -> Result<Vec<i32>, DecodingError> {
+        let coeffs = self.location_coefficients(axis, locs, None)?;
+        let ps_candidates: Vec<Vec<i64>> = self.sns_index.iter().enumerate()
+            .map(|(i, sns_index)| {
+                let coeff_seq: Vec<i8> = coeffs.iter().map(|c| c[i]).collect();
+                sns_index.find_all(&coeff_seq).into_iter().map(|p| p as i64).collect()
+            })
+            .collect();
+
+        cartesian_product(&ps_candidates, MAX_CORRECTION_CANDIDATES)
+            .ok_or(DecodingError::CorrectionAmbiguous)?
+            .iter()
+            .map(|ps| self.crt.solve(ps).map(|x| x as i32))
+            .collect()
+    }
+
+    // SNS coefficients of the deltas between per-row MNS locations, erased
+    // next to an unknown location
+    fn location_coefficients(&self, axis: Coordinate, locs: &[Option<i32>], mut trace: Option<&mut AxisReport>) -> Result<Vec<Vec<i8>>, DecodingError> {
+        // Compute differences, unknown next to an unknown location
+        let diffs: Vec<Option<i32>> = locs.windows(2)
+            .map(|w| match (w[1], w[0]) {
+                (Some(curr), Some(prev)) => Some((curr - prev + self.mns_length as i32) % self.mns_length as i32),
+                _ => None,
+            })
+            .collect();
+        if let Some(t) = trace.as_deref_mut() {
+            t.deltas = diffs.clone();
+        }
+
+        let mut deltae = Vec::new();
+        for (i, diff) in diffs.into_iter().enumerate() {
+            match diff {
+                Some(diff) if diff < self.delta_range.0 || diff > self.delta_range.1 => {
+                    return Err(DecodingError::DeltaOutOfRange { axis, row: i + 1, value: diff });
+                }
+                Some(diff) => deltae.push(Some(diff - self.delta_range.0)),
+                None => deltae.push(None),
+            }
+        }
+
+        // Project to coefficients, unknown deltas become erased coefficients
+        let coeffs: Vec<Vec<i8>> = deltae.iter()
+            .map(|d| match d {
+                Some(d) => self.num_basis.project(&[*d as i64]).remove(0),
+                None => vec![ERASED; self.sns.len()],
+            })
+            .collect();
+        if let Some(t) = trace {
+            t.coefficients = coeffs.clone();
+        }
+        Ok(coeffs)
+    }
+
+    // Decode the position from a window of at least mns_order x mns_order dots,
+    // using every overlapping sub-window to outvote misread dots. Returns the
+    // position of the top-left dot and the number of dots that disagreed with
+    // the corrected pattern. Positions that are not clearly better than every
+    // other candidate are rejected with CorrectionAmbiguous rather than
+    // guessed. Every two rows and columns beyond mns_order let the window
+    // correct one more dot, and max_corrections is limited to that: a 6x6
+    // window corrects nothing, 8x8 most single misread dots and 10x10 most
+    // pairs. More misread dots than that are rejected, unless they happen to
+    // turn the window into a valid one elsewhere, which decodes like any
+    // clean window.
+    pub fn decode_position_corrected(&self, bits: &BitMatrix, max_corrections: usize) -> Result<((i32, i32), usize), DecodingError> {
+        self.check_shape(bits)?;
+        let correctable = (bits.rows().min(bits.cols()) - self.mns_order) / 2;
+        let max_corrections = max_corrections.min(correctable);
+        let bits = bits.as_array();
+
+        let x_bits = bits.slice(s![.., .., 0]).t().to_owned();
+        let (x, x_corrections) = self.decode_position_along_direction_corrected(Coordinate::X, &x_bits, max_corrections)?;
+
+        let y_bits = bits.slice(s![.., .., 1]).to_owned();
+        let (y, y_corrections) = self.decode_position_along_direction_corrected(Coordinate::Y, &y_bits, max_corrections - x_corrections)?;
+
+        Ok(((x, y), x_corrections + y_corrections))
+    }
+
+    fn decode_position_along_direction_corrected(&self, axis: Coordinate, bits: &Array2<i8>, max_corrections: usize) -> Result<(i32, usize), DecodingError> {
+        let rows: Vec<Vec<i8>> = bits.axis_iter(Axis(0)).map(|row| row.to_vec()).collect();
+
+        // Every dot of a row votes for the MNS locations it agrees with
+        let locs: Vec<usize> = rows.iter()
+            .map(|row| {
+                (0..self.mns_length)
+                    .min_by_key(|&loc| count_cyclic_mismatches(&self.mns, loc, row))
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        // Deltas outside the valid range stem from misread rows and are
+        // treated as unknown coefficients
+        let coeffs: Vec<Option<Vec<i8>>> = locs.windows(2)
+            .map(|w| {
+                let diff = ((w[1] + self.mns_length - w[0]) % self.mns_length) as i32;
+                if diff < self.delta_range.0 || diff > self.delta_range.1 {
+                    None
+                } else {
+                    let d = (diff - self.delta_range.0) as i64;
+                    self.num_basis.project(&[d]).pop()
+                }
+            })
+            .collect();
+
+        // Each secondary sequence proposes the positions agreeing best with
+        // the known coefficients
+        let mut ps_candidates: Vec<Vec<i64>> = Vec::new();
+        for (i, sns) in self.sns.iter().enumerate() {
+            let coeff_seq: Vec<Option<i8>> = coeffs.iter().map(|c| c.as_ref().map(|c| c[i])).collect();
+            let scores: Vec<usize> = (0..sns.len())
+                .map(|p| {
+                    coeff_seq.iter().enumerate()
+                        .filter(|&(r, c)| *c == Some(sns[(p + r) % sns.len()]))
+                        .count()
+                })
+                .collect();
+            let best = scores.iter().copied().max().unwrap_or(0);
+            ps_candidates.push(
+                (0..sns.len()).filter(|&p| scores[p] == best).map(|p| p as i64).collect()
+            );
+        }
+
+        let size = self.section_size() as i32;
+        let mut candidates = Vec::new();
+        for ps in cartesian_product(&ps_candidates, MAX_CORRECTION_CANDIDATES).unwrap_or_default() {
+            candidates.push(self.crt.solve(&ps)? as i32);
+        }
+
+        // Rows matching the MNS exactly are decoded like a window with the
+        // other rows erased, and with up to max_corrections rows erased in
+        // total. One of these sets leaves out every misread row, so the true
+        // position is a candidate whenever the window can be corrected at all.
+        let exact: Vec<Option<i32>> = rows.iter()
+            .map(|row| match self.mns_index.find_all(row).as_slice() {
+                &[loc] => Some(loc as i32),
+                _ => None,
+            })
+            .collect();
+        let known: Vec<usize> = (0..rows.len()).filter(|&r| exact[r].is_some()).collect();
+        let spare = (max_corrections + known.len()).checked_sub(rows.len());
+        let erasures = spare.map_or_else(Vec::new, |k| erasure_subsets(known.len(), k, MAX_CORRECTION_CANDIDATES));
+        for erased in erasures {
+            let mut locs = exact.clone();
+            for i in erased {
+                locs[known[i]] = None;
+            }
+            // Sets leaving too many positions to list are skipped, the cost
+            // margin below decides whether the rest suffice
+            if let Ok(positions) = self.consistent_positions(axis, &locs) {
+                candidates.extend(positions);
+            }
+        }
+
+        // Error-free mns_order x mns_order sub-windows vote for their position
+        let (n_rows, n_cols) = bits.dim();
+        for r in 0..=(n_rows - self.mns_order) {
+            for c in 0..=(n_cols - self.mns_order) {
+                let sub = bits.slice(s![r..r + self.mns_order, c..c + self.mns_order]).to_owned();
+                if let Ok(pos) = self.decode_position_along_direction(axis, &sub, None) {
+                    candidates.push((pos - r as i32).rem_euclid(size));
+                }
+            }
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        // Check every candidate for delta consistency with all rows at once.
+        // The one leaving the fewest dots to correct has to beat the runner-up
+        // by a margin, otherwise a misread dot may have made a wrong position
+        // look right.
+        let mut costs: Vec<(usize, i32)> = candidates.into_iter()
+            .map(|pos| (self.correction_cost(&rows, pos), pos))
+            .collect();
+        costs.sort_unstable();
+
+        match costs.as_slice() {
+            [] => Err(DecodingError::NoConsistentPosition),
+            [(corrections, _), ..] if *corrections > max_corrections => {
+                Err(DecodingError::TooManyCorrections { corrections: *corrections, max_corrections })
+            }
+            [(best, _), (next, _), ..] if next - best < MIN_CORRECTION_MARGIN => {
+                Err(DecodingError::CorrectionAmbiguous)
+            }
+            [(corrections, pos), ..] => Ok((*pos, *corrections)),
+        }
+    }
+
+    // Number of dots that disagree with the pattern at the given position, for
+    // the best choice of the first row's MNS location
+    fn correction_cost(&self, rows: &[Vec<i8>], pos: i32) -> usize {
+        let mut offsets = vec![0usize; rows.len()];
+        for r in 1..rows.len() {
+            let d = self.delta(pos + r as i32 - 1) as usize;
+            offsets[r] = (offsets[r - 1] + d) % self.mns_length;
+        }
+
+        (0..self.mns_length)
+            .map(|loc| {
+                rows.iter().zip(offsets.iter())
+                    .map(|(row, &o)| count_cyclic_mismatches(&self.mns, (loc + o) % self.mns_length, row))
+                    .sum::<usize>()
+            })
+            .min()
+            .unwrap_or(0)
+    }
+
+    pub fn decode_section(&self, bits: &BitMatrix, pos: (i32, i32)) -> Result<(i32, i32), DecodingError> {
+        self.check_shape(bits)?;
+        let sub_bits = self.decoding_window(bits.as_array());
+        let (col, px_mns) = self.first_known_location(Coordinate::X, sub_bits.slice(s![.., .., 0]).t())?;
+        let (row, py_mns) = self.first_known_location(Coordinate::Y, sub_bits.slice(s![.., .., 1]))?;
 
-        let px_mns = find_subsequence(&self.mns_cyclic, &px_seq)
-            .ok_or_else(|| DecodingError::new("Failed to find x sequence in MNS"))?;
-        let py_mns = find_subsequence(&self.mns_cyclic, &py_seq)
-            .ok_or_else(|| DecodingError::new("Failed to find y sequence in MNS"))?;
+        let sx = self.integrate_roll(pos.0 + col as i32, 0);
+        let sy = self.integrate_roll(pos.1 + row as i32, 0);
 
-        let sx = self.integrate_roll(pos.0, 0);
-        let sy = self.integrate_roll(pos.1, 0);
+        // The x-bits run down the columns, so the MNS location of a column is
+        // shifted by the window's row (pos.1) on top of the rolls accumulated
+        // over the preceding columns, and vice versa for y.
+        // Sections are only defined modulo the MNS length.
 
-        let section_x = (px_mns as i32 - pos.1 - sx) % self.mns_length as i32;
-        let section_y = (py_mns as i32 - pos.0 - sy) % self.mns_length as i32;
+        let section_x = (px_mns as i32 - pos.1 - sx).rem_euclid(self.mns_length as i32);
+        let section_y = (py_mns as i32 - pos.0 - sy).rem_euclid(self.mns_length as i32);
 
         Ok((section_x, section_y))
     }
 
+    // Index and MNS location of the first line whose location erased dots
+    // leave unambiguous, where lines are the columns of the x-bits or the
+    // rows of the y-bits
+    fn first_known_location(&self, axis: Coordinate, lines: ArrayView2<i8>) -> Result<(usize, usize), DecodingError> {
+        for (i, line) in lines.axis_iter(Axis(0)).enumerate() {
+            match self.mns_index.find_unique(&line.to_vec()) {
+                Ok(Some(loc)) => return Ok((i, loc)),
+                Ok(None) => return Err(DecodingError::MnsMiss { axis, row: i }),
+                Err(_) => {}
+            }
+        }
+        Err(DecodingError::Ambiguous)
+    }
+
+    // Decode position and section of many upright windows, on all cores with
+    // the parallel feature. Results keep the order of the windows.
+    pub fn decode_batch(&self, windows: &[BitMatrix]) -> Vec<Result<Decoded, DecodingError>> {
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            windows.par_iter().map(|w| self.decode_upright(w)).collect()
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            windows.iter().map(|w| self.decode_upright(w)).collect()
+        }
+    }
+
+    fn decode_upright(&self, bits: &BitMatrix) -> Result<Decoded, DecodingError> {
+        let position = self.decode_position(bits)?;
+        let section = self.decode_section(bits, position)?;
+        Ok(Decoded {
+            position,
+            section,
+            rotation: 0,
+        })
+    }
+
+    // Decode the global address of the top-left dot of an upright window
+    pub fn decode_address(&self, bits: &BitMatrix) -> Result<DotAddress, DecodingError> {
+        let decoded = self.decode_upright(bits)?;
+        Ok(self.dot_address(decoded.section, decoded.position))
+    }
+
+    // Slide the decoding window across a whole bit-matrix and map every dot to
+    // its absolute position, flagging dots whose windows or neighbours
+    // disagree, e.g. because of misprints or tampering
+    pub fn decode_position_map(&self, bits: &BitMatrix) -> PositionMap {
+        let order = self.mns_order;
+        let windows_shape = (
+            (bits.rows() + 1).saturating_sub(order),
+            (bits.cols() + 1).saturating_sub(order),
+        );
+        let windows = Array2::from_shape_fn(windows_shape, |(y, x)| {
+            self.decode_position(&bits.window((x, y), (order, order))).ok()
+        });
+
+        PositionMap::from_windows(&windows, bits.shape(), order)
+    }
+
+    // Encode a bit-matrix of the given shape for a section and check that every
+    // mns_order x mns_order window in it decodes back to its own position and
+    // to the section, taken modulo the MNS length
+    pub fn verify_roundtrip(&self, shape: (usize, usize), section: (i32, i32)) -> Result<(), DecodingError> {
+        if shape.0 < self.mns_order || shape.1 < self.mns_order {
+            return Err(DecodingError::ShapeMismatch { shape, order: self.mns_order });
+        }
+
+        let bits = self.encode_bitmatrix(shape, section);
+        let expected_section = (
+            section.0.rem_euclid(self.mns_length as i32),
+            section.1.rem_euclid(self.mns_length as i32),
+        );
+
+        for y in 0..=(shape.0 - self.mns_order) {
+            for x in 0..=(shape.1 - self.mns_order) {
+                let window = bits.window((x, y), (self.mns_order, self.mns_order));
+
+                let pos = self.decode_position(&window)?;
+                if pos != (x as i32, y as i32) {
+                    return Err(DecodingError::PositionMismatch { window: (x, y), decoded: pos });
+                }
+
+                let sec = self.decode_section(&window, pos)?;
+                if sec != expected_section {
+                    return Err(DecodingError::SectionMismatch {
+                        window: (x, y),
+                        decoded: sec,
+                        expected: expected_section,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Sum of all deltas before pos, added to first_roll, in constant time.
+    // Each delta is delta_range.0 plus the mixed-radix sum of one coefficient
+    // per SNS, and each SNS repeats with its own length, so the coefficients
+    // summed up to pos are whole periods plus a precomputed prefix sum.
+    // Negative positions continue the sequences periodically to the left.
     fn integrate_roll(&self, pos: i32, first_roll: i32) -> i32 {
-        let mut r = 0i64;
-        for i in 0..pos {
-            r += self.delta(i) as i64;
+        let pos = pos as i64;
+        let modulus = self.mns_length as i64;
+
+        let mut r = pos.rem_euclid(modulus) * (self.delta_range.0 as i64).rem_euclid(modulus);
+        let mut base = 1i64;
+        let terms = self.sns_prefix_sums.iter().zip(&self.sns_lengths).zip(&self.num_basis.factors);
+        for ((prefix, &len), &factor) in terms {
+            let len = len as i64;
+            let sum = pos.div_euclid(len) * prefix[len as usize] + prefix[pos.rem_euclid(len) as usize];
+            r = (r + sum.rem_euclid(modulus) * base) % modulus;
+            base = (base * factor) % modulus;
+        }
+
+        (first_roll as i64 + r).rem_euclid(modulus) as i32
+    }
+
+    // Determine how many counter-clockwise quarter turns bring the observed
+    // bit-matrix upright, i.e. the `k` for which `rot90(bits, k)` decodes.
+    // Every column of x-bits and every row of y-bits of the full window must
+    // be found in the MNS, so windows larger than mns_order disambiguate better.
+    // The upright orientation always decodes, so a wrong rotation is never
+    // returned, but a 6x6 window of the Anoto pattern carries too little
+    // redundancy and about half of them fail with RotationAmbiguous. 7x7
+    // windows are ambiguous in rare cases, 8x8 windows practically never.
+    pub fn decode_rotation(&self, bits: &BitMatrix) -> Result<usize, DecodingError> {
+        let mut candidates = Vec::new();
+        for k in 0..4 {
+            let rbits = rot90(bits, k);
+            if self.is_upright(&rbits) {
+                candidates.push(k);
+            }
         }
-        ((first_roll as i64 + r) % self.mns_length as i64) as i32
+
+        match candidates.as_slice() {
+            [k] => Ok(*k),
+            [] => Err(DecodingError::NoRotation),
+            _ => Err(DecodingError::RotationAmbiguous),
+        }
+    }
+
+    fn is_upright(&self, bits: &BitMatrix) -> bool {
+        if bits.rows() < self.mns_order || bits.cols() < self.mns_order {
+            return false;
+        }
+
+        let x_ok = bits.as_array().slice(s![.., .., 0]).axis_iter(Axis(1))
+            .all(|col| self.mns_index.find(&col.to_vec()).is_some());
+        let y_ok = bits.as_array().slice(s![.., .., 1]).axis_iter(Axis(0))
+            .all(|row| self.mns_index.find(&row.to_vec()).is_some());
+
+        x_ok && y_ok && self.decode_position(bits).is_ok()
+    }
+
+    // Decode position, section and rotation of an arbitrarily oriented window.
+    // Position and section refer to the upright window `rot90(bits, rotation)`.
+    // Pass at least 8x8 dots, see decode_rotation.
+    pub fn decode(&self, bits: &BitMatrix) -> Result<Decoded, DecodingError> {
+        let rotation = self.decode_rotation(bits)?;
+        let upright = rot90(bits, rotation);
+        let position = self.decode_position(&upright)?;
+        let section = self.decode_section(&upright, position)?;
+
+        Ok(Decoded {
+            position,
+            section,
+            rotation,
+        })
+    }
+}
+
+// Result of a rotation-aware decode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decoded {
+    pub position: (i32, i32),
+    pub section: (i32, i32),
+    pub rotation: usize,
+}
+
+impl Decoded {
+    // Paper coordinates in millimetres of the decoded grid point
+    pub fn position_mm(&self, geometry: &GridGeometry) -> (f64, f64) {
+        geometry.grid_point(self.position)
     }
 }
 
@@ -355,22 +1028,16 @@ pub fn anoto_6x6_a4_fixed() -> AnotoCodec {
         2, 0, 1, 2, 0, 1, 0, 1, 1, 0, 2, 0, 1, 1, 0, 1, 0, 1, 0, 0, 1
     ];
 
-    AnotoCodec::new(
-        mns,
-        6,
-        vec![a1, a2, a3, a4_alt],
-        vec![3i32, 3i32, 2i32, 3i32],
-        (5, 58),
-    )
+    AnotoCodec::builder()
+        .mns(mns, 6)
+        .sns(vec![a1, a2, a3, a4_alt])
+        .pfactors(vec![3i32, 3i32, 2i32, 3i32])
+        .delta_range((5, 58))
+        .build()
+        .expect("Anoto patent sequences are valid")
 }
 
 // Helper functions
-fn make_cyclic(seq: &[i8], order: usize) -> Vec<i8> {
-    let mut result = seq.to_vec();
-    result.extend_from_slice(&seq[0..(order-1)]);
-    result
-}
-
 fn rotate_vec(vec: &[i8], shift: isize) -> Vec<i8> {
     let len = vec.len() as isize;
     let shift = ((shift % len) + len) % len;
@@ -391,9 +1058,89 @@ fn rotate_vec(vec: &[i8], shift: isize) -> Vec<i8> {
     result
 }
 
-fn find_subsequence(haystack: &[i8], needle: &[i8]) -> Option<usize> {
-    haystack.windows(needle.len())
-        .position(|window| window == needle)
+// Running totals of seq, starting with 0 and ending with the sum of all values
+fn prefix_sums(seq: &[i8]) -> Vec<i64> {
+    let mut sums = Vec::with_capacity(seq.len() + 1);
+    sums.push(0);
+    for &v in seq {
+        sums.push(sums[sums.len() - 1] + v as i64);
+    }
+    sums
+}
+
+// Rotate a bit-matrix by k quarter turns counter-clockwise. Turning the paper
+// also turns every dot displacement (up -> left -> down -> right -> up), which
+// swaps the roles of the x and y bits: x' = y, y' = 1 - x. Working on the bits
+// keeps a single erased bit erased without losing the other one.
+pub fn rot90(bits: &BitMatrix, k: usize) -> BitMatrix {
+    let mut m = bits.as_array().to_owned();
+    for _ in 0..(k % 4) {
+        let (rows, cols, _) = m.dim();
+        let mut r = Array3::<i8>::zeros((cols, rows, 2));
+        for y in 0..cols {
+            for x in 0..rows {
+                let (x_bit, y_bit) = (m[[x, cols - 1 - y, 0]], m[[x, cols - 1 - y, 1]]);
+                r[[y, x, 0]] = y_bit;
+                r[[y, x, 1]] = if x_bit == ERASED { ERASED } else { 1 - x_bit };
+            }
+        }
+        m = r;
+    }
+    BitMatrix::new(m).expect("rotation preserves bit values")
+}
+
+// Every set of at most k of the indices 0..n, smallest sets first, stopping
+// after limit sets
+fn erasure_subsets(n: usize, k: usize, limit: usize) -> Vec<Vec<usize>> {
+    let mut subsets = vec![Vec::new()];
+    let mut frontier = vec![Vec::new()];
+    for _ in 0..k.min(n) {
+        let mut next = Vec::new();
+        for subset in &frontier {
+            let from = subset.last().map_or(0, |&i: &usize| i + 1);
+            for i in from..n {
+                let mut grown: Vec<usize> = subset.clone();
+                grown.push(i);
+                next.push(grown);
+            }
+        }
+        subsets.extend(next.iter().cloned());
+        if subsets.len() >= limit {
+            subsets.truncate(limit);
+            break;
+        }
+        frontier = next;
+    }
+    subsets
+}
+
+// All ways of picking one value per slot, or None if there are more than limit
+fn cartesian_product<T: Copy>(choices: &[Vec<T>], limit: usize) -> Option<Vec<Vec<T>>> {
+    let count = choices.iter()
+        .try_fold(1usize, |acc, c| acc.checked_mul(c.len()))
+        .filter(|&n| n <= limit)?;
+
+    let combinations = (0..count)
+        .map(|mut rest| {
+            choices.iter()
+                .map(|c| {
+                    let v = c[rest % c.len()];
+                    rest /= c.len();
+                    v
+                })
+                .collect()
+        })
+        .collect();
+    Some(combinations)
+}
+
+// Number of positions where needle differs from the cyclic sequence starting
+// at start, not counting erased needle values
+fn count_cyclic_mismatches(seq: &[i8], start: usize, needle: &[i8]) -> usize {
+    needle.iter().enumerate()
+        .filter(|&(i, &v)| v != ERASED && seq[(start + i) % seq.len()] != v)
+        .count()
 }
 
+#[cfg(feature = "plotting")]
 pub mod plotting;
\ No newline at end of file
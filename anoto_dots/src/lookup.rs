@@ -0,0 +1,148 @@
+use crate::builder::CodecError;
+use crate::{DecodingError, ERASED};
+
+// Largest lookup table built for a sequence, in entries
+pub const MAX_TABLE_SIZE: usize = 1 << 20;
+
+// Lookup table from every window of a cyclic sequence to its position, so that
+// locating a partial sequence is a table access instead of a linear scan.
+// Sequences with repeated windows only keep the first position of each, and
+// are scanned instead.
+pub struct WindowIndex {
+    seq: Vec<i8>,
+    order: usize,
+    base: usize,
+    table: Vec<Option<u32>>,
+    repeated: bool,
+}
+
+impl WindowIndex {
+    // Index the windows of seq. Fails if the table for every window of the
+    // given order over the values of seq would exceed MAX_TABLE_SIZE.
+    pub fn new(seq: &[i8], order: usize) -> Result<Self, CodecError> {
+        let base = alphabet_size(seq);
+        let mut table = vec![None; table_size(seq, order)?];
+        let mut repeated = false;
+
+        for pos in 0..seq.len() {
+            let window: Vec<i8> = (0..order).map(|i| seq[(pos + i) % seq.len()]).collect();
+            if let Some(key) = pack(&window, base) {
+                repeated |= table[key].is_some();
+                table[key].get_or_insert(pos as u32);
+            }
+        }
+
+        Ok(WindowIndex {
+            seq: seq.to_vec(),
+            order,
+            base,
+            table,
+            repeated,
+        })
+    }
+
+    // All positions at which needle occurs cyclically. Needles may be longer
+    // than the order and contain erased values, which match anything.
+    pub fn find_all(&self, needle: &[i8]) -> Vec<usize> {
+        if needle.len() < self.order || self.repeated {
+            return self.scan(needle);
+        }
+
+        // Anchor the lookup on the window of the needle with the fewest erasures
+        let (offset, erased) = (0..=(needle.len() - self.order))
+            .map(|o| (o, needle[o..o + self.order].iter().filter(|&&v| v == ERASED).count()))
+            .min_by_key(|&(_, e)| e)
+            .unwrap_or((0, 0));
+
+        let fillings = self.base.checked_pow(erased as u32).unwrap_or(usize::MAX);
+        if fillings > self.seq.len() {
+            return self.scan(needle);
+        }
+
+        let anchor = &needle[offset..offset + self.order];
+        let mut found = Vec::new();
+        for mut filling in 0..fillings {
+            let window: Vec<i8> = anchor.iter()
+                .map(|&v| {
+                    if v != ERASED {
+                        return v;
+                    }
+                    let filled = (filling % self.base) as i8;
+                    filling /= self.base;
+                    filled
+                })
+                .collect();
+
+            let Some(pos) = pack(&window, self.base).and_then(|key| self.table[key]) else {
+                continue;
+            };
+            let start = (pos as usize + self.seq.len() - offset % self.seq.len()) % self.seq.len();
+            if self.matches_at(start, needle) {
+                found.push(start);
+            }
+        }
+
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    pub fn find(&self, needle: &[i8]) -> Option<usize> {
+        self.find_all(needle).first().copied()
+    }
+
+    // Like find, but fails if erasures in the needle allow more than one match
+    pub fn find_unique(&self, needle: &[i8]) -> Result<Option<usize>, DecodingError> {
+        let found = self.find_all(needle);
+        if found.len() > 1 {
+            return Err(DecodingError::Ambiguous);
+        }
+        Ok(found.first().copied())
+    }
+
+    fn scan(&self, needle: &[i8]) -> Vec<usize> {
+        find_linear(&self.seq, needle)
+    }
+
+    fn matches_at(&self, start: usize, needle: &[i8]) -> bool {
+        matches_at(&self.seq, start, needle)
+    }
+}
+
+// All positions at which needle occurs cyclically, found by comparing it at
+// every position of seq. Gives the same result as WindowIndex::find_all.
+pub fn find_linear(seq: &[i8], needle: &[i8]) -> Vec<usize> {
+    (0..seq.len())
+        .filter(|&start| matches_at(seq, start, needle))
+        .collect()
+}
+
+fn matches_at(seq: &[i8], start: usize, needle: &[i8]) -> bool {
+    needle.iter().enumerate()
+        .all(|(i, &v)| v == ERASED || seq[(start + i) % seq.len()] == v)
+}
+
+// Number of distinct values a window key has to cover, at least two
+fn alphabet_size(seq: &[i8]) -> usize {
+    seq.iter().map(|&v| v.max(0) as usize + 1).max().unwrap_or(0).max(2)
+}
+
+// Entries of the lookup table for the windows of seq of the given order
+pub(crate) fn table_size(seq: &[i8], order: usize) -> Result<usize, CodecError> {
+    let alphabet = alphabet_size(seq);
+    u32::try_from(order).ok()
+        .and_then(|order| alphabet.checked_pow(order))
+        .filter(|&size| size <= MAX_TABLE_SIZE)
+        .ok_or(CodecError::OrderTooLarge { order, alphabet })
+}
+
+// Pack a window into its table key, None if it holds values outside the alphabet
+fn pack(window: &[i8], base: usize) -> Option<usize> {
+    window.iter().try_fold(0usize, |key, &v| {
+        if v < 0 || v as usize >= base {
+            None
+        } else {
+            Some(key * base + v as usize)
+        }
+    })
+}
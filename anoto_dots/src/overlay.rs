@@ -0,0 +1,140 @@
+use crate::bitmatrix::BitMatrix;
+use crate::layout::PageLayout;
+use crate::raster::{PngColour, Raster, encode_png, render_page};
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+
+// Artwork pixels with every channel at least this bright count as blank paper
+pub(crate) const PAPER_WHITE: u8 = 250;
+
+// Which of pattern and artwork is printed on top
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layering {
+    // Dots only show where the artwork leaves the paper blank, so that text
+    // and logos stay clean
+    PatternUnder,
+    // Dots are printed over everything, artwork included
+    PatternOver,
+}
+
+// Page artwork as 8-bit RGB pixels, row by row. The artwork always covers
+// the whole page and is stretched to the size of the output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artwork {
+    width: u32,
+    height: u32,
+    rgb: Vec<u8>,
+}
+
+impl Artwork {
+    // None if rgb does not hold exactly three bytes per pixel
+    pub fn from_rgb(width: u32, height: u32, rgb: Vec<u8>) -> Option<Self> {
+        if rgb.len() != width as usize * height as usize * 3 {
+            return None;
+        }
+        Some(Artwork { width, height, rgb })
+    }
+
+    // Blank paper of the given size
+    pub fn blank(width: u32, height: u32) -> Self {
+        Artwork {
+            width,
+            height,
+            rgb: vec![255; width as usize * height as usize * 3],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn rgb(&self) -> &[u8] {
+        &self.rgb
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        let i = (y as usize * self.width as usize + x as usize) * 3;
+        [self.rgb[i], self.rgb[i + 1], self.rgb[i + 2]]
+    }
+
+    pub fn is_blank(&self, x: u32, y: u32) -> bool {
+        self.pixel(x, y).iter().all(|&c| c >= PAPER_WHITE)
+    }
+
+    // Load a PNG of any colour type. Transparent parts become white paper.
+    pub fn load_png(filename: &str) -> Result<Artwork, Box<dyn Error>> {
+        let mut decoder = png::Decoder::new(File::open(filename)?);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf)?;
+
+        let over_white = |c: u8, alpha: u8| ((c as u32 * alpha as u32 + 255 * (255 - alpha as u32)) / 255) as u8;
+        let rgb: Vec<u8> = match info.color_type {
+            png::ColorType::Grayscale => buf[..info.buffer_size()].iter().flat_map(|&g| [g, g, g]).collect(),
+            png::ColorType::GrayscaleAlpha => buf[..info.buffer_size()]
+                .chunks(2)
+                .flat_map(|p| [over_white(p[0], p[1]); 3])
+                .collect(),
+            png::ColorType::Rgb => buf[..info.buffer_size()].to_vec(),
+            png::ColorType::Rgba => buf[..info.buffer_size()]
+                .chunks(4)
+                .flat_map(|p| [over_white(p[0], p[3]), over_white(p[1], p[3]), over_white(p[2], p[3])])
+                .collect(),
+            png::ColorType::Indexed => return Err("Indexed PNG was not expanded".into()),
+        };
+        Ok(Artwork { width: info.width, height: info.height, rgb })
+    }
+
+    // RGB PNG with the resolution of the page it was composited for
+    pub fn to_png(&self, dpi: u32) -> Result<Vec<u8>, Box<dyn Error>> {
+        let stride = self.width as usize * 3;
+        let rows = self.rgb.chunks(stride.max(1)).map(|row| row.to_vec());
+        encode_png((self.width, self.height), dpi, PngColour::Rgb, rows)
+    }
+
+    pub fn save_png(&self, filename: &str, dpi: u32) -> Result<(), Box<dyn Error>> {
+        let mut file = File::create(filename)?;
+        file.write_all(&self.to_png(dpi)?)?;
+        Ok(())
+    }
+
+    // Nearest pixel of the artwork stretched to (width, height)
+    fn sample(&self, x: u32, y: u32, width: u32, height: u32) -> (u32, u32) {
+        (
+            (x as u64 * self.width as u64 / width as u64) as u32,
+            (y as u64 * self.height as u64 / height as u64) as u32,
+        )
+    }
+}
+
+// Combine a rendered pattern with artwork into one image of the raster's size
+pub fn overlay_raster(raster: &Raster, artwork: &Artwork, layering: Layering) -> Artwork {
+    let (width, height) = (raster.width(), raster.height());
+    let mut out = Artwork::blank(width, height);
+    if artwork.width == 0 || artwork.height == 0 {
+        return out;
+    }
+
+    for y in 0..height {
+        for x in 0..width {
+            let (ax, ay) = artwork.sample(x, y, width, height);
+            let dot = raster.is_black(x, y) && (layering == Layering::PatternOver || artwork.is_blank(ax, ay));
+            let colour = if dot { [0, 0, 0] } else { artwork.pixel(ax, ay) };
+            let i = (y as usize * width as usize + x as usize) * 3;
+            out.rgb[i..i + 3].copy_from_slice(&colour);
+        }
+    }
+    out
+}
+
+// Render a pattern page at the layout's resolution and combine it with
+// artwork covering the whole paper
+pub fn overlay_page(layout: &PageLayout, bitmatrix: &BitMatrix, artwork: &Artwork, layering: Layering) -> Artwork {
+    overlay_raster(&render_page(layout, bitmatrix), artwork, layering)
+}
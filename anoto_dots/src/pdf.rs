@@ -0,0 +1,263 @@
+use crate::bitmatrix::BitMatrix;
+use crate::geometry::{Unit, format_length as num};
+use crate::layout::PageLayout;
+use crate::overlay::{Artwork, Layering, PAPER_WHITE};
+use crate::pdf_import::ImportedPage;
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::Write;
+
+// A printable PDF of pattern pages, written without any external tools.
+// Dots are vector circles at their physical positions, painted in the black
+// of the CMYK key channel only, since pens see carbon black but not the
+// mixed black of the colour channels.
+#[derive(Debug, Clone, Default)]
+pub struct PdfDocument {
+    pages: Vec<PdfPage>,
+}
+
+#[derive(Debug, Clone)]
+struct PdfPage {
+    size_pt: (f64, f64),
+    content: Vec<u8>,
+    artwork: Option<PageArtwork>,
+}
+
+// Artwork printed on a page together with the pattern
+#[derive(Debug, Clone, PartialEq)]
+pub enum PageArtwork {
+    // An image stretched over the whole paper
+    Image(Artwork),
+    // A page of another PDF, its media box stretched over the whole paper
+    Page(ImportedPage),
+}
+
+impl From<&Artwork> for PageArtwork {
+    fn from(artwork: &Artwork) -> Self {
+        PageArtwork::Image(artwork.clone())
+    }
+}
+
+impl From<&ImportedPage> for PageArtwork {
+    fn from(page: &ImportedPage) -> Self {
+        PageArtwork::Page(page.clone())
+    }
+}
+
+impl PageArtwork {
+    // Objects written after the page's content stream
+    fn object_count(&self) -> usize {
+        match self {
+            PageArtwork::Image(_) => 1,
+            PageArtwork::Page(page) => 1 + page.object_count(),
+        }
+    }
+}
+
+impl PdfDocument {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    // Add a page of the layout's paper size with the dots of bitmatrix inside
+    // its margins, e.g. from PageLayout::encode_page
+    pub fn add_page(&mut self, layout: &PageLayout, bitmatrix: &BitMatrix, dot_diameter_mm: f64) {
+        let (width, height) = layout.paper.dimensions_mm();
+        self.pages.push(PdfPage {
+            size_pt: (width * Unit::Point.per_mm(), height * Unit::Point.per_mm()),
+            content: dot_content(layout, bitmatrix, dot_diameter_mm).into_bytes(),
+            artwork: None,
+        });
+    }
+
+    // Add a page like add_page with artwork stretched over the whole paper.
+    // Blank parts of an image are masked out, so with the pattern under the
+    // artwork the dots show through wherever the paper is blank. A PDF page
+    // hides the dots only where it paints, unless it fills its background.
+    pub fn add_page_with_artwork(
+        &mut self,
+        layout: &PageLayout,
+        bitmatrix: &BitMatrix,
+        dot_diameter_mm: f64,
+        artwork: impl Into<PageArtwork>,
+        layering: Layering,
+    ) {
+        let artwork = artwork.into();
+        let (width, height) = layout.paper.dimensions_mm();
+        let size_pt = (width * Unit::Point.per_mm(), height * Unit::Point.per_mm());
+
+        // Images fill the unit square, forms draw in their media box
+        let (scale, origin) = match &artwork {
+            PageArtwork::Image(_) => (size_pt, (0.0, 0.0)),
+            PageArtwork::Page(page) => {
+                let (w, h) = page.size_pt();
+                let [left, bottom, ..] = page.media_box();
+                let scale = (size_pt.0 / w, size_pt.1 / h);
+                (scale, (-left * scale.0, -bottom * scale.1))
+            }
+        };
+        let image = format!(
+            "q {} 0 0 {} {} {} cm /Art Do Q\n",
+            num(scale.0),
+            num(scale.1),
+            num(origin.0),
+            num(origin.1)
+        );
+        let dots = format!("q\n{}Q\n", dot_content(layout, bitmatrix, dot_diameter_mm));
+        let content = match layering {
+            Layering::PatternUnder => dots + &image,
+            Layering::PatternOver => image + &dots,
+        };
+
+        self.pages.push(PdfPage {
+            size_pt,
+            content: content.into_bytes(),
+            artwork: Some(artwork),
+        });
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        // Objects are the catalog, the page tree, then a page, its content
+        // stream and its artwork, if any, for every page. Imported pages are
+        // followed by the objects their resources refer to.
+        let mut out: Vec<u8> = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = Vec::new();
+
+        let mut page_ids = Vec::new();
+        let mut next_id = 3;
+        for page in &self.pages {
+            page_ids.push(next_id);
+            next_id += 2 + page.artwork.as_ref().map_or(0, PageArtwork::object_count);
+        }
+
+        let kids: Vec<String> = page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+        offsets.push(out.len());
+        out.extend_from_slice(b"1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n");
+        offsets.push(out.len());
+        out.extend_from_slice(format!(
+            "2 0 obj\n<< /Type /Pages /Kids [{}] /Count {} >>\nendobj\n",
+            kids.join(" "),
+            self.pages.len()
+        ).as_bytes());
+
+        for (page, &page_id) in self.pages.iter().zip(&page_ids) {
+            let resources = match page.artwork {
+                Some(_) => format!("<< /XObject << /Art {} 0 R >> >>", page_id + 2),
+                None => "<< >>".to_string(),
+            };
+            offsets.push(out.len());
+            out.extend_from_slice(format!(
+                "{} 0 obj\n<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources {} /Contents {} 0 R >>\nendobj\n",
+                page_id,
+                num(page.size_pt.0),
+                num(page.size_pt.1),
+                resources,
+                page_id + 1
+            ).as_bytes());
+
+            offsets.push(out.len());
+            write_stream(&mut out, page_id + 1, "", &page.content)?;
+
+            match &page.artwork {
+                Some(PageArtwork::Image(artwork)) => {
+                    let dict = format!(
+                        "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Mask [{w} 255 {w} 255 {w} 255] ",
+                        artwork.width(),
+                        artwork.height(),
+                        w = PAPER_WHITE
+                    );
+                    offsets.push(out.len());
+                    write_stream(&mut out, page_id + 2, &dict, artwork.rgb())?;
+                }
+                Some(PageArtwork::Page(imported)) => {
+                    let [left, bottom, right, top] = imported.media_box();
+                    let dict = format!(
+                        "/Type /XObject /Subtype /Form /BBox [{} {} {} {}] /Resources {} ",
+                        num(left),
+                        num(bottom),
+                        num(right),
+                        num(top),
+                        imported.resources(page_id + 3)
+                    );
+                    offsets.push(out.len());
+                    write_stream(&mut out, page_id + 2, &dict, imported.content())?;
+                    imported.write_objects(&mut out, &mut offsets, page_id + 3);
+                }
+                None => {}
+            }
+        }
+
+        let xref = out.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1);
+        for offset in &offsets {
+            let _ = writeln!(table, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            table,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            offsets.len() + 1,
+            xref
+        );
+        out.extend_from_slice(table.as_bytes());
+        Ok(out)
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        let mut file = File::create(filename)?;
+        file.write_all(&self.to_bytes()?)?;
+        Ok(())
+    }
+}
+
+// Drawing operators for the dots of a page, in millimetres from the top-left
+// corner. Every dot is a zero-length line, which round caps paint as a
+// filled circle.
+fn dot_content(layout: &PageLayout, bitmatrix: &BitMatrix, dot_diameter_mm: f64) -> String {
+    let height = layout.paper.dimensions_mm().1;
+    let geometry = layout.page_grid();
+
+    let mut content = String::new();
+    let points = Unit::Point.per_mm();
+    let _ = writeln!(content, "{} 0 0 {} 0 {} cm", num(points), num(-points), num(height * points));
+    let _ = writeln!(content, "0 0 0 1 K 1 J {} w", num(dot_diameter_mm));
+    for y in 0..bitmatrix.rows() {
+        for x in 0..bitmatrix.cols() {
+            let Some(dot) = bitmatrix.dot(y, x) else { continue };
+            let (cx, cy) = geometry.dot_centre((x as i32, y as i32), dot);
+            let (cx, cy) = (num(cx), num(cy));
+            let _ = writeln!(content, "{} {} m {} {} l", cx, cy, cx, cy);
+        }
+    }
+    content.push_str("S\n");
+    content
+}
+
+// Compressed stream object with extra dictionary entries
+fn write_stream(out: &mut Vec<u8>, id: usize, dict: &str, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    let stream = encoder.finish()?;
+    out.extend_from_slice(format!(
+        "{} 0 obj\n<< {}/Length {} /Filter /FlateDecode >>\nstream\n",
+        id,
+        dict,
+        stream.len()
+    ).as_bytes());
+    out.extend_from_slice(&stream);
+    out.extend_from_slice(b"\nendstream\nendobj\n");
+    Ok(())
+}
+
+// Save a single pattern page as PDF
+pub fn save_page_pdf(layout: &PageLayout, bitmatrix: &BitMatrix, dot_diameter_mm: f64, filename: &str) -> Result<(), Box<dyn Error>> {
+    let mut document = PdfDocument::new();
+    document.add_page(layout, bitmatrix, dot_diameter_mm);
+    document.save(filename)
+}
@@ -0,0 +1,771 @@
+use flate2::read::ZlibDecoder;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::Write as _;
+use std::io::Read;
+
+// Deepest nesting of page trees and objects that is followed before a file
+// is considered broken
+const MAX_DEPTH: usize = 64;
+
+// Largest decompressed stream that is read, so that a small file cannot
+// inflate into all available memory
+const MAX_DECODED_SIZE: u64 = 32 << 20;
+
+// A page of an existing PDF, ready to be placed on a pattern page as a Form
+// XObject. The page's content and every object its resources refer to are
+// copied, so the imported page no longer depends on the source file. Only
+// unencrypted files with Flate-compressed or uncompressed content of at most
+// MAX_DECODED_SIZE bytes per stream are read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedPage {
+    media_box: [f64; 4],
+    content: Vec<u8>,
+    resources: Object,
+    // Copies of the objects the resources refer to, where Ref(i) is the i-th
+    objects: Vec<Object>,
+}
+
+impl ImportedPage {
+    // Import the page with the given index, counting from zero
+    pub fn load(filename: &str, page: usize) -> Result<ImportedPage, Box<dyn Error>> {
+        Self::from_bytes(&std::fs::read(filename)?, page)
+    }
+
+    pub fn from_bytes(data: &[u8], page: usize) -> Result<ImportedPage, Box<dyn Error>> {
+        if !data.starts_with(b"%PDF-") {
+            return Err("Not a PDF file".into());
+        }
+        let document = Document::parse(data)?;
+        if get(&document.trailer, b"Encrypt").is_some() {
+            return Err("Encrypted PDFs are not supported".into());
+        }
+
+        let root = match get(&document.trailer, b"Root") {
+            Some(root) => document.resolve(root),
+            None => document.objects.values()
+                .find(|obj| matches!(obj, Object::Dict(d) if get(d, b"Type") == Some(&Object::Name(b"Catalog".to_vec()))))
+                .ok_or("PDF has no document catalog")?,
+        };
+        let pages_root = match root {
+            Object::Dict(d) => get(d, b"Pages").ok_or("PDF has no page tree")?,
+            _ => return Err("PDF has no document catalog".into()),
+        };
+
+        let mut pages = Vec::new();
+        document.collect_pages(pages_root, Inherited::default(), &mut pages, &mut HashSet::new(), 0)?;
+        let count = pages.len();
+        let info = pages.into_iter().nth(page)
+            .ok_or_else(|| format!("PDF has {} pages, no page {}", count, page))?;
+
+        let rotate = info.rotate.as_ref().and_then(|r| document.resolve(r).as_f64()).unwrap_or(0.0);
+        if rotate.rem_euclid(360.0) != 0.0 {
+            return Err("Rotated pages are not supported".into());
+        }
+
+        let media_box = match info.media_box.as_ref().map(|b| document.resolve(b)) {
+            Some(Object::Array(values)) if values.len() == 4 => {
+                let v: Vec<f64> = values.iter()
+                    .map(|v| document.resolve(v).as_f64().ok_or("MediaBox is not numeric"))
+                    .collect::<Result<_, _>>()?;
+                [v[0].min(v[2]), v[1].min(v[3]), v[0].max(v[2]), v[1].max(v[3])]
+            }
+            _ => return Err("Page has no MediaBox".into()),
+        };
+        if media_box[2] - media_box[0] <= 0.0 || media_box[3] - media_box[1] <= 0.0 {
+            return Err("Page has an empty MediaBox".into());
+        }
+
+        // Several content streams are concatenated with whitespace in between
+        let streams: Vec<&Object> = match info.contents.as_ref().map(|c| document.resolve(c)) {
+            None | Some(Object::Null) => Vec::new(),
+            Some(Object::Array(parts)) => parts.iter().map(|p| document.resolve(p)).collect(),
+            Some(stream) => vec![stream],
+        };
+        let mut content = Vec::new();
+        for stream in streams {
+            let Object::Stream(dict, data) = stream else {
+                return Err("Page content is not a stream".into());
+            };
+            content.extend_from_slice(&document.decode_stream(dict, data)?);
+            content.push(b'\n');
+        }
+
+        let mut importer = Importer { document: &document, ids: HashMap::new(), objects: Vec::new() };
+        let resources = match info.resources.as_ref().map(|r| document.resolve(r)) {
+            Some(resources @ Object::Dict(_)) => importer.import(resources, 0),
+            _ => Object::Dict(Vec::new()),
+        };
+
+        Ok(ImportedPage { media_box, content, resources, objects: importer.objects })
+    }
+
+    // Width and height of the page in points
+    pub fn size_pt(&self) -> (f64, f64) {
+        (self.media_box[2] - self.media_box[0], self.media_box[3] - self.media_box[1])
+    }
+
+    // Lower-left and upper-right corner of the page in its own coordinates
+    pub fn media_box(&self) -> [f64; 4] {
+        self.media_box
+    }
+
+    // Decoded content stream, drawing in the coordinates of the media box
+    pub fn content(&self) -> &[u8] {
+        &self.content
+    }
+
+    // Number of objects copied along with the page's resources
+    pub fn object_count(&self) -> usize {
+        self.objects.len()
+    }
+
+    // Resource dictionary with the copied objects numbered from first_id
+    pub(crate) fn resources(&self, first_id: usize) -> String {
+        let mut out = String::new();
+        self.resources.write(&mut out, first_id);
+        out
+    }
+
+    // Write the copied objects numbered from first_id, recording the offset
+    // of every object
+    pub(crate) fn write_objects(&self, out: &mut Vec<u8>, offsets: &mut Vec<usize>, first_id: usize) {
+        for (i, object) in self.objects.iter().enumerate() {
+            offsets.push(out.len());
+            let mut head = format!("{} 0 obj\n", first_id + i);
+            match object {
+                Object::Stream(dict, data) => {
+                    write_dict(&mut head, dict, first_id, Some(data.len()));
+                    head.push_str("\nstream\n");
+                    out.extend_from_slice(head.as_bytes());
+                    out.extend_from_slice(data);
+                    out.extend_from_slice(b"\nendstream\nendobj\n");
+                }
+                object => {
+                    object.write(&mut head, first_id);
+                    head.push_str("\nendobj\n");
+                    out.extend_from_slice(head.as_bytes());
+                }
+            }
+        }
+    }
+}
+
+type Dict = Vec<(Vec<u8>, Object)>;
+
+// A PDF object. Names hold their decoded bytes, reals their original text so
+// that they are written back unchanged, and references only the object
+// number.
+#[derive(Debug, Clone, PartialEq)]
+enum Object {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Real(String),
+    Name(Vec<u8>),
+    Str(Vec<u8>),
+    Array(Vec<Object>),
+    Dict(Dict),
+    Ref(u32),
+    Stream(Dict, Vec<u8>),
+}
+
+fn get<'a>(dict: &'a Dict, key: &[u8]) -> Option<&'a Object> {
+    dict.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+impl Object {
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Object::Int(v) => Some(*v as f64),
+            Object::Real(v) => v.parse().ok(),
+            _ => None,
+        }
+    }
+
+    // PDF syntax with references to copied object i numbered first_id + i.
+    // Names and strings are escaped, so the output is plain ASCII.
+    fn write(&self, out: &mut String, first_id: usize) {
+        match self {
+            Object::Null => out.push_str("null"),
+            Object::Bool(v) => out.push_str(if *v { "true" } else { "false" }),
+            Object::Int(v) => out.push_str(&v.to_string()),
+            Object::Real(v) => out.push_str(v),
+            Object::Name(name) => write_name(out, name),
+            Object::Str(bytes) => {
+                out.push('<');
+                for b in bytes {
+                    let _ = write!(out, "{:02x}", b);
+                }
+                out.push('>');
+            }
+            Object::Array(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(' ');
+                    }
+                    item.write(out, first_id);
+                }
+                out.push(']');
+            }
+            Object::Dict(entries) => write_dict(out, entries, first_id, None),
+            Object::Ref(i) => out.push_str(&format!("{} 0 R", first_id + *i as usize)),
+            // Streams are only written as objects of their own
+            Object::Stream(..) => out.push_str("null"),
+        }
+    }
+
+}
+
+// Dictionary, with the length of the data if it is a stream dictionary
+fn write_dict(out: &mut String, entries: &Dict, first_id: usize, stream_length: Option<usize>) {
+    out.push_str("<<");
+    for (key, value) in entries {
+        out.push(' ');
+        write_name(out, key);
+        out.push(' ');
+        value.write(out, first_id);
+    }
+    if let Some(length) = stream_length {
+        let _ = write!(out, " /Length {}", length);
+    }
+    out.push_str(" >>");
+}
+
+fn write_name(out: &mut String, name: &[u8]) {
+    out.push('/');
+    for &b in name {
+        if b.is_ascii_graphic() && b != b'#' && !is_delimiter(b) {
+            out.push(b as char);
+        } else {
+            let _ = write!(out, "#{:02X}", b);
+        }
+    }
+}
+
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, 0 | b'\t' | b'\n' | 0x0c | b'\r' | b' ')
+}
+
+fn is_delimiter(b: u8) -> bool {
+    matches!(b, b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%')
+}
+
+// Reads objects from PDF syntax
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Self {
+        Parser { data, pos }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b) = self.peek() {
+            if is_whitespace(b) {
+                self.pos += 1;
+            } else if b == b'%' {
+                while self.peek().is_some_and(|b| b != b'\n' && b != b'\r') {
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    // Run of regular characters, empty at a delimiter
+    fn token(&mut self) -> &'a [u8] {
+        let start = self.pos;
+        while self.peek().is_some_and(|b| !is_whitespace(b) && !is_delimiter(b)) {
+            self.pos += 1;
+        }
+        &self.data[start..self.pos]
+    }
+
+    fn keyword(&mut self, keyword: &[u8]) -> bool {
+        let start = self.pos;
+        self.skip_whitespace();
+        if self.token() == keyword {
+            true
+        } else {
+            self.pos = start;
+            false
+        }
+    }
+
+    fn unsigned(&mut self) -> Option<u32> {
+        let start = self.pos;
+        self.skip_whitespace();
+        let token = self.token();
+        match std::str::from_utf8(token).ok().filter(|t| t.bytes().all(|b| b.is_ascii_digit())) {
+            Some(t) if !t.is_empty() => t.parse().ok(),
+            _ => {
+                self.pos = start;
+                None
+            }
+        }
+    }
+
+    // The number of an indirect object starting here, "N G obj"
+    fn object_header(&mut self) -> Option<u32> {
+        let start = self.pos;
+        let header = self.unsigned()
+            .and_then(|num| self.unsigned().map(|_| num))
+            .filter(|_| self.keyword(b"obj"));
+        if header.is_none() {
+            self.pos = start;
+        }
+        header
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Object, Box<dyn Error>> {
+        if depth > MAX_DEPTH {
+            return Err("PDF objects are nested too deeply".into());
+        }
+        self.skip_whitespace();
+        match self.peek().ok_or("Unexpected end of PDF")? {
+            b'/' => {
+                self.pos += 1;
+                Ok(Object::Name(decode_name(self.token())))
+            }
+            b'(' => self.literal_string(),
+            b'<' if self.data.get(self.pos + 1) == Some(&b'<') => {
+                self.pos += 2;
+                let mut dict = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    if self.data[self.pos..].starts_with(b">>") {
+                        self.pos += 2;
+                        return Ok(Object::Dict(dict));
+                    }
+                    let Object::Name(key) = self.object(depth + 1)? else {
+                        return Err("PDF dictionary key is not a name".into());
+                    };
+                    let value = self.object(depth + 1)?;
+                    dict.push((key, value));
+                }
+            }
+            b'<' => {
+                self.pos += 1;
+                let mut digits = Vec::new();
+                loop {
+                    match self.peek().ok_or("Unterminated PDF hex string")? {
+                        b'>' => break,
+                        b if b.is_ascii_hexdigit() => digits.push(b),
+                        _ => {}
+                    }
+                    self.pos += 1;
+                }
+                self.pos += 1;
+                if digits.len() % 2 == 1 {
+                    digits.push(b'0');
+                }
+                Ok(Object::Str(digits.chunks(2).map(|p| hex_value(p[0]) << 4 | hex_value(p[1])).collect()))
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() == Some(b']') {
+                        self.pos += 1;
+                        return Ok(Object::Array(items));
+                    }
+                    items.push(self.object(depth + 1)?);
+                }
+            }
+            b'0'..=b'9' | b'+' | b'-' | b'.' => {
+                let token = std::str::from_utf8(self.token())?.to_string();
+                let Ok(value) = token.parse::<i64>() else {
+                    return Ok(Object::Real(token));
+                };
+
+                // "N G R" is a reference
+                let after = self.pos;
+                if let Ok(num) = u32::try_from(value)
+                    && self.unsigned().is_some()
+                    && self.keyword(b"R")
+                {
+                    return Ok(Object::Ref(num));
+                }
+                self.pos = after;
+                Ok(Object::Int(value))
+            }
+            _ => match self.token() {
+                b"true" => Ok(Object::Bool(true)),
+                b"false" => Ok(Object::Bool(false)),
+                b"null" => Ok(Object::Null),
+                token => Err(format!("Unexpected PDF token {:?}", String::from_utf8_lossy(token)).into()),
+            },
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<Object, Box<dyn Error>> {
+        self.pos += 1;
+        let mut bytes = Vec::new();
+        let mut nesting = 0;
+        loop {
+            let b = self.peek().ok_or("Unterminated PDF string")?;
+            self.pos += 1;
+            match b {
+                b'(' => {
+                    nesting += 1;
+                    bytes.push(b);
+                }
+                b')' if nesting == 0 => return Ok(Object::Str(bytes)),
+                b')' => {
+                    nesting -= 1;
+                    bytes.push(b);
+                }
+                b'\\' => {
+                    let e = self.peek().ok_or("Unterminated PDF string")?;
+                    self.pos += 1;
+                    match e {
+                        b'n' => bytes.push(b'\n'),
+                        b'r' => bytes.push(b'\r'),
+                        b't' => bytes.push(b'\t'),
+                        b'b' => bytes.push(8),
+                        b'f' => bytes.push(12),
+                        b'0'..=b'7' => {
+                            let mut value = (e - b'0') as u32;
+                            for _ in 0..2 {
+                                match self.peek() {
+                                    Some(d @ b'0'..=b'7') => {
+                                        value = value * 8 + (d - b'0') as u32;
+                                        self.pos += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            bytes.push(value as u8);
+                        }
+                        // A backslash at the end of a line continues the string
+                        b'\r' => {
+                            if self.peek() == Some(b'\n') {
+                                self.pos += 1;
+                            }
+                        }
+                        b'\n' => {}
+                        e => bytes.push(e),
+                    }
+                }
+                b => bytes.push(b),
+            }
+        }
+    }
+
+    // Data of a stream whose dictionary was just read. The stream keyword is
+    // followed by an end of line, and the data by endstream. A length that
+    // is an indirect object or does not end at endstream is ignored in
+    // favour of searching for endstream.
+    fn stream_data(&mut self, dict: &Dict) -> Result<Vec<u8>, Box<dyn Error>> {
+        if self.peek() == Some(b'\r') {
+            self.pos += 1;
+        }
+        if self.peek() == Some(b'\n') {
+            self.pos += 1;
+        }
+        let start = self.pos;
+
+        if let Some(Object::Int(length)) = get(dict, b"Length")
+            && let Some(end) = start.checked_add(*length as usize).filter(|&end| *length >= 0 && end <= self.data.len())
+        {
+            self.pos = end;
+            if self.keyword(b"endstream") {
+                return Ok(self.data[start..end].to_vec());
+            }
+        }
+
+        let found = find(self.data, b"endstream", start).ok_or("Unterminated PDF stream")?;
+        let mut end = found;
+        if end > start && self.data[end - 1] == b'\n' {
+            end -= 1;
+        }
+        if end > start && self.data[end - 1] == b'\r' {
+            end -= 1;
+        }
+        self.pos = found + b"endstream".len();
+        Ok(self.data[start..end].to_vec())
+    }
+}
+
+fn hex_value(b: u8) -> u8 {
+    (b as char).to_digit(16).unwrap_or(0) as u8
+}
+
+fn decode_name(raw: &[u8]) -> Vec<u8> {
+    let mut name = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        if raw[i] == b'#' && i + 2 < raw.len() && raw[i + 1].is_ascii_hexdigit() && raw[i + 2].is_ascii_hexdigit() {
+            name.push(hex_value(raw[i + 1]) << 4 | hex_value(raw[i + 2]));
+            i += 3;
+        } else {
+            name.push(raw[i]);
+            i += 1;
+        }
+    }
+    name
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack.get(from..)?.windows(needle.len()).position(|w| w == needle).map(|i| i + from)
+}
+
+// Attributes a page inherits from the page tree nodes above it
+#[derive(Debug, Clone, Default)]
+struct Inherited {
+    resources: Option<Object>,
+    media_box: Option<Object>,
+    rotate: Option<Object>,
+}
+
+#[derive(Debug)]
+struct PageInfo {
+    resources: Option<Object>,
+    media_box: Option<Object>,
+    rotate: Option<Object>,
+    contents: Option<Object>,
+}
+
+// All objects of a file by number, and the trailer dictionary
+struct Document {
+    objects: HashMap<u32, Object>,
+    trailer: Dict,
+}
+
+impl Document {
+    // Objects are read in file order rather than through the cross-reference
+    // table, so that files with a broken table can be read as well. Later
+    // definitions replace earlier ones, as in incremental updates.
+    fn parse(data: &[u8]) -> Result<Document, Box<dyn Error>> {
+        let mut objects = HashMap::new();
+        let mut trailer = Vec::new();
+        let mut parser = Parser::new(data, 0);
+
+        loop {
+            parser.skip_whitespace();
+            if parser.peek().is_none() {
+                break;
+            }
+            // Malformed objects are skipped, the parser has moved past their
+            // header either way
+            if let Some(num) = parser.object_header() {
+                let Ok(mut object) = parser.object(0) else { continue };
+                if let Object::Dict(dict) = &object
+                    && parser.keyword(b"stream")
+                {
+                    let data = parser.stream_data(dict)?;
+                    object = Object::Stream(dict.clone(), data);
+                }
+                parser.keyword(b"endobj");
+
+                // Cross-reference streams take the place of the trailer
+                if let Object::Stream(dict, _) = &object
+                    && get(dict, b"Type") == Some(&Object::Name(b"XRef".to_vec()))
+                {
+                    trailer = dict.clone();
+                }
+                objects.insert(num, object);
+            } else if parser.keyword(b"trailer") {
+                if let Ok(Object::Dict(dict)) = parser.object(0) {
+                    trailer = dict;
+                }
+            } else if parser.token().is_empty() {
+                // Stray delimiters outside of objects
+                parser.pos += 1;
+            }
+        }
+
+        let mut document = Document { objects, trailer };
+        document.expand_object_streams();
+        Ok(document)
+    }
+
+    // Add the objects compressed into object streams, unless they are
+    // defined outside of one. Malformed object streams are skipped like
+    // malformed objects.
+    fn expand_object_streams(&mut self) {
+        let streams: Vec<(Dict, Vec<u8>)> = self.objects.values()
+            .filter_map(|obj| match obj {
+                Object::Stream(dict, data) if get(dict, b"Type") == Some(&Object::Name(b"ObjStm".to_vec())) => {
+                    Some((dict.clone(), data.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        for (dict, data) in streams {
+            let Ok(objects) = self.read_object_stream(&dict, &data) else { continue };
+            for (num, object) in objects {
+                self.objects.entry(num).or_insert(object);
+            }
+        }
+    }
+
+    // The numbered objects of an object stream, leaving out any that do not
+    // parse
+    fn read_object_stream(&self, dict: &Dict, data: &[u8]) -> Result<Vec<(u32, Object)>, Box<dyn Error>> {
+        let data = self.decode_stream(dict, data)?;
+        let count = match get(dict, b"N") {
+            Some(Object::Int(n)) if *n >= 0 => *n as usize,
+            _ => return Err("Object stream without /N".into()),
+        };
+        let first = match get(dict, b"First") {
+            Some(Object::Int(n)) if *n >= 0 && (*n as u64) < data.len() as u64 => *n as usize,
+            _ => return Err("Object stream without a valid /First".into()),
+        };
+
+        let mut objects = Vec::new();
+        let mut header = Parser::new(&data[..first], 0);
+        for _ in 0..count {
+            let (Some(num), Some(offset)) = (header.unsigned(), header.unsigned()) else { break };
+            let Some(start) = first.checked_add(offset as usize).filter(|&start| start < data.len()) else {
+                continue;
+            };
+            if let Ok(object) = Parser::new(&data, start).object(0) {
+                objects.push((num, object));
+            }
+        }
+        Ok(objects)
+    }
+
+    // The object a reference points to, Null for missing objects
+    fn resolve<'a>(&'a self, mut object: &'a Object) -> &'a Object {
+        for _ in 0..MAX_DEPTH {
+            match object {
+                Object::Ref(num) => object = self.objects.get(num).unwrap_or(&Object::Null),
+                _ => return object,
+            }
+        }
+        &Object::Null
+    }
+
+    fn decode_stream(&self, dict: &Dict, data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        let filters = match get(dict, b"Filter").map(|f| self.resolve(f)) {
+            None | Some(Object::Null) => Vec::new(),
+            Some(Object::Array(filters)) => filters.iter().map(|f| self.resolve(f).clone()).collect(),
+            Some(filter) => vec![filter.clone()],
+        };
+        if get(dict, b"DecodeParms").is_some_and(|p| !matches!(self.resolve(p), Object::Null)) {
+            return Err("PDF streams with decode parameters are not supported".into());
+        }
+
+        let mut data = data.to_vec();
+        for filter in filters {
+            match filter {
+                Object::Name(name) if name == b"FlateDecode" || name == b"Fl" => {
+                    let mut decoded = Vec::new();
+                    ZlibDecoder::new(&data[..]).take(MAX_DECODED_SIZE + 1).read_to_end(&mut decoded)?;
+                    if decoded.len() as u64 > MAX_DECODED_SIZE {
+                        return Err(format!("PDF stream decodes to more than {} bytes", MAX_DECODED_SIZE).into());
+                    }
+                    data = decoded;
+                }
+                Object::Name(name) => {
+                    return Err(format!("PDF filter {} is not supported", String::from_utf8_lossy(&name)).into());
+                }
+                _ => return Err("PDF filter is not a name".into()),
+            }
+        }
+        Ok(data)
+    }
+
+    // Pages under node in document order
+    fn collect_pages(
+        &self,
+        node: &Object,
+        inherited: Inherited,
+        pages: &mut Vec<PageInfo>,
+        visited: &mut HashSet<u32>,
+        depth: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        if depth > MAX_DEPTH {
+            return Err("PDF page tree is nested too deeply".into());
+        }
+        if let Object::Ref(num) = node
+            && !visited.insert(*num)
+        {
+            return Err("PDF page tree has a cycle".into());
+        }
+        let Object::Dict(dict) = self.resolve(node) else {
+            return Err("PDF page tree node is not a dictionary".into());
+        };
+
+        let inherited = Inherited {
+            resources: get(dict, b"Resources").cloned().or(inherited.resources),
+            media_box: get(dict, b"MediaBox").cloned().or(inherited.media_box),
+            rotate: get(dict, b"Rotate").cloned().or(inherited.rotate),
+        };
+        match get(dict, b"Kids").map(|k| self.resolve(k)) {
+            Some(Object::Array(kids)) => {
+                for kid in kids {
+                    self.collect_pages(kid, inherited.clone(), pages, visited, depth + 1)?;
+                }
+            }
+            _ => pages.push(PageInfo {
+                resources: inherited.resources,
+                media_box: inherited.media_box,
+                rotate: inherited.rotate,
+                contents: get(dict, b"Contents").cloned(),
+            }),
+        }
+        Ok(())
+    }
+}
+
+// Copies objects out of a document, numbering them in the order they are
+// first referenced
+struct Importer<'a> {
+    document: &'a Document,
+    ids: HashMap<u32, u32>,
+    objects: Vec<Object>,
+}
+
+impl Importer<'_> {
+    // Copy of object with references to copied objects. Parent links are
+    // dropped so that the page tree of the source is left behind, and stream
+    // lengths are written anew.
+    fn import(&mut self, object: &Object, depth: usize) -> Object {
+        if depth > MAX_DEPTH {
+            return Object::Null;
+        }
+        match object {
+            Object::Ref(num) => {
+                if let Some(&id) = self.ids.get(num) {
+                    return Object::Ref(id);
+                }
+                let Some(target) = self.document.objects.get(num) else {
+                    return Object::Null;
+                };
+                let id = self.objects.len() as u32;
+                self.ids.insert(*num, id);
+                self.objects.push(Object::Null);
+                self.objects[id as usize] = self.import(target, depth + 1);
+                Object::Ref(id)
+            }
+            Object::Array(items) => Object::Array(items.iter().map(|item| self.import(item, depth + 1)).collect()),
+            Object::Dict(dict) => Object::Dict(self.import_dict(dict, depth)),
+            Object::Stream(dict, data) => {
+                let dict: Dict = dict.iter().filter(|(key, _)| key != b"Length").cloned().collect();
+                Object::Stream(self.import_dict(&dict, depth), data.clone())
+            }
+            object => object.clone(),
+        }
+    }
+
+    fn import_dict(&mut self, dict: &Dict, depth: usize) -> Dict {
+        dict.iter()
+            .filter(|(key, _)| key != b"Parent")
+            .map(|(key, value)| (key.clone(), self.import(value, depth + 1)))
+            .collect()
+    }
+}
@@ -1,15 +1,18 @@
+use crate::bitmatrix::BitMatrix;
 use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
 use ndarray::Array3;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Write;
 use std::error::Error;
 
 #[derive(Serialize, Deserialize)]
-struct BitMatrix {
+struct BitMatrixFile {
     data: Vec<Vec<Vec<i8>>>,
 }
 
-pub fn save_bitmatrix_text(bitmatrix: &Array3<i8>, filename: &str) -> Result<(), Box<dyn Error>> {
+pub fn save_bitmatrix_text(bitmatrix: &BitMatrix, filename: &str) -> Result<(), Box<dyn Error>> {
+    let bitmatrix = bitmatrix.as_array();
     let mut content = String::new();
     content.push_str("G = array([\n");
     for (i, row) in bitmatrix.outer_iter().enumerate() {
@@ -41,13 +44,46 @@ pub fn save_bitmatrix_text(bitmatrix: &Array3<i8>, filename: &str) -> Result<(),
     Ok(())
 }
 
-pub fn save_bitmatrix_json(bitmatrix: &Array3<i8>, filename: &str) -> Result<(), Box<dyn Error>> {
-    let data: Vec<Vec<Vec<i8>>> = bitmatrix.outer_iter().map(|row| {
+pub fn save_bitmatrix_json(bitmatrix: &BitMatrix, filename: &str) -> Result<(), Box<dyn Error>> {
+    let data: Vec<Vec<Vec<i8>>> = bitmatrix.as_array().outer_iter().map(|row| {
         row.outer_iter().map(|col| col.to_vec()).collect()
     }).collect();
-    let bm = BitMatrix { data };
+    let bm = BitMatrixFile { data };
     let json = serde_json::to_string_pretty(&bm)?;
     let mut file = File::create(filename)?;
     file.write_all(json.as_bytes())?;
     Ok(())
-}
\ No newline at end of file
+}
+
+// Read a bit-matrix written by save_bitmatrix_json, checking its values
+pub fn load_bitmatrix_json(filename: &str) -> Result<BitMatrix, Box<dyn Error>> {
+    let bm: BitMatrixFile = read_json(filename)?;
+    let rows = bm.data.len();
+    let cols = bm.data.first().map_or(0, |row| row.len());
+    let channels = bm.data.first().and_then(|row| row.first()).map_or(2, |dot| dot.len());
+    if bm.data.iter().any(|row| row.len() != cols) || bm.data.iter().flatten().any(|dot| dot.len() != channels) {
+        return Err("Rows of the bit-matrix differ in length".into());
+    }
+    let values: Vec<i8> = bm.data.into_iter().flatten().flatten().collect();
+    let bits = Array3::from_shape_vec((rows, cols, channels), values)?;
+    Ok(BitMatrix::new(bits)?)
+}
+
+pub(crate) fn read_json<T: DeserializeOwned>(filename: &str) -> Result<T, Box<dyn Error>> {
+    Ok(serde_json::from_str(&fs::read_to_string(filename)?)?)
+}
+
+// Replace a file through a temporary file next to it, so that a crash never
+// leaves a truncated file behind
+pub(crate) fn write_atomic(filename: &str, content: &[u8]) -> Result<(), Box<dyn Error>> {
+    let temp = format!("{}.tmp", filename);
+    let mut file = File::create(&temp)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    fs::rename(&temp, filename)?;
+    Ok(())
+}
+
+pub(crate) fn write_json_atomic<T: Serialize + ?Sized>(filename: &str, value: &T) -> Result<(), Box<dyn Error>> {
+    write_atomic(filename, serde_json::to_string_pretty(value)?.as_bytes())
+}
@@ -1,75 +1,148 @@
+use crate::bitmatrix::{BitMatrix, Dot};
+use crate::geometry::GridGeometry;
 use plotters::prelude::*;
 use std::error::Error;
 
+// Chrome around the plot in pixels
+const MARGIN: u32 = 15;
+const LABEL_AREA: u32 = 40;
+const CAPTION_FONT: u32 = 40;
+
+// How dots are coloured
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DotColours {
+    PerDirection { up: RGBColor, right: RGBColor, left: RGBColor, down: RGBColor },
+    Monochrome(RGBColor),
+}
+
+impl DotColours {
+    pub fn colour(&self, dot: Dot) -> RGBColor {
+        match *self {
+            DotColours::PerDirection { up, right, left, down } => match dot {
+                Dot::Up => up,
+                Dot::Right => right,
+                Dot::Left => left,
+                Dot::Down => down,
+            },
+            DotColours::Monochrome(colour) => colour,
+        }
+    }
+}
+
+impl Default for DotColours {
+    fn default() -> Self {
+        DotColours::PerDirection { up: BLACK, right: RED, left: BLUE, down: GREEN }
+    }
+}
+
+// How draw_dots renders a bitmatrix. The grid pitch and the dot displacement
+// are taken from geometry, the plot covers the grid with a margin of one pitch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOptions {
+    pub geometry: GridGeometry,
+    // Image size in pixels, by default fitted to the matrix at
+    // pixels_per_pitch
+    pub size: Option<(u32, u32)>,
+    pub pixels_per_pitch: u32,
+    pub dot_radius: u32,
+    pub colours: DotColours,
+    pub axes: bool,
+    pub caption: Option<String>,
+    // Also write the bitmatrix to bitmatrix.txt and bitmatrix.json in the
+    // working directory, as draw_dots has always done
+    pub save_bitmatrix: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            geometry: GridGeometry::default(),
+            size: None,
+            pixels_per_pitch: 40,
+            dot_radius: 5,
+            colours: DotColours::default(),
+            axes: true,
+            caption: Some("Anoto Dots".to_string()),
+            save_bitmatrix: true,
+        }
+    }
+}
+
+impl RenderOptions {
+    // Image size in pixels for a bitmatrix of the given (rows, columns)
+    pub fn image_size(&self, shape: (usize, usize)) -> (u32, u32) {
+        if let Some(size) = self.size {
+            return size;
+        }
+        let mut chrome = (2 * MARGIN, 2 * MARGIN);
+        if self.axes {
+            chrome.0 += LABEL_AREA;
+            chrome.1 += LABEL_AREA;
+        }
+        if self.caption.is_some() {
+            chrome.1 += CAPTION_FONT + MARGIN;
+        }
+        let plot = |cells: usize| (cells as u32 + 1) * self.pixels_per_pitch;
+        (plot(shape.1) + chrome.0, plot(shape.0) + chrome.1)
+    }
+}
+
 // Drawing function using plotters
 pub fn draw_dots(
-    bitmatrix: &ndarray::Array3<i8>,
-    _grid_size: f64,
+    bitmatrix: &BitMatrix,
+    options: &RenderOptions,
     filename: &str,
 ) -> Result<(), Box<dyn Error>> {
-    // Persist the bitmatrix
-    crate::persist::save_bitmatrix_text(bitmatrix, "bitmatrix.txt")?;
-    crate::persist::save_bitmatrix_json(bitmatrix, "bitmatrix.json")?;
+    if options.save_bitmatrix {
+        crate::persist::save_bitmatrix_text(bitmatrix, "bitmatrix.txt")?;
+        crate::persist::save_bitmatrix_json(bitmatrix, "bitmatrix.json")?;
+    }
+
+    let geometry = &options.geometry;
+    let size = options.image_size((bitmatrix.rows(), bitmatrix.cols()));
+    let root_area = BitMapBackend::new(filename, size).into_drawing_area();
+    root_area.fill(&WHITE)?;
 
-    let root_area = BitMapBackend::new(filename, (800, 400))
-    .into_drawing_area();
-    root_area.fill(&WHITE).unwrap();
+    // Paper area of the grid with a margin of one pitch, in millimetres
+    let (left, top) = geometry.grid_point((-1, -1));
+    let (right, bottom) = geometry.grid_point((bitmatrix.cols() as i32, bitmatrix.rows() as i32));
 
-    let mut ctx = ChartBuilder::on(&root_area)
-        .margin(15)
-        .set_label_area_size(LabelAreaPosition::Left, 40)
-        .set_label_area_size(LabelAreaPosition::Bottom, 40)
-        .caption("Anoto Dots", ("sans-serif", 40))
-        .build_cartesian_2d(-10_i32..170_i32, 100_i32..-10_i32)
-        .unwrap();
+    let mut builder = ChartBuilder::on(&root_area);
+    builder.margin(MARGIN);
+    if options.axes {
+        builder
+            .set_label_area_size(LabelAreaPosition::Left, LABEL_AREA)
+            .set_label_area_size(LabelAreaPosition::Bottom, LABEL_AREA);
+    }
+    if let Some(caption) = &options.caption {
+        builder.caption(caption, ("sans-serif", CAPTION_FONT));
+    }
+    // A descending y range flips the axis so that rows grow downwards
+    let mut ctx = builder.build_cartesian_2d(left..right, bottom..top)?;
 
-    ctx.configure_mesh()
-        .x_labels(18)
-        .x_label_formatter(&|v| format!("{}", (v / 10) ))
-        .y_labels(12)
-        .y_label_formatter(&|v| format!("{}", (v / 10) ))
-        .draw().unwrap();
+    if options.axes {
+        ctx.configure_mesh()
+            .x_labels(18)
+            .x_label_formatter(&|v| format!("{:.1}", v))
+            .y_labels(12)
+            .y_label_formatter(&|v| format!("{:.1}", v))
+            .x_desc("mm")
+            .draw()?;
+    }
 
-   // Draw circles based on bitmatrix values
+    // Draw circles based on bitmatrix values, skipping erased dots
     ctx.draw_series(
-        (0..bitmatrix.dim().0).flat_map(|y| {
-            (0..bitmatrix.dim().1).map(move |x| {
-                let x_bit = bitmatrix[[y, x, 0]] as usize;
-                let y_bit = bitmatrix[[y, x, 1]] as usize;
-                let dot_type = x_bit + (y_bit << 1);
-                let color = match dot_type {
-                    0 => &BLACK, // UP
-                    1 => &RED,   // RIGHT
-                    2 => &BLUE,  // LEFT
-                    3 => &GREEN, // DOWN
-                    _ => &BLACK,
-                };
-                let mut x_x :i32 = x.clone() as i32;
-                let mut y_y :i32 = y.clone() as i32;
-                match dot_type {
-                    0 => { // UP
-                        x_x = x_x * 10;
-                        y_y = y_y * 10 + 2;
-                    }
-                    1 => { // RIGHT
-                        x_x = x_x * 10 + 2;
-                        y_y = y_y * 10;
-                    }
-                    2 => { // LEFT
-                        x_x = (x_x * 10) - 2;
-                        y_y = y_y * 10;
-                    },
-                    3 => { // DOWN
-                        x_x = x_x * 10;
-                        y_y = (y_y * 10) - 2;
-                    },
-                    _ => {}
-                };
+        (0..bitmatrix.rows()).flat_map(|y| {
+            (0..bitmatrix.cols()).filter_map(move |x| {
+                let dot = bitmatrix.dot(y, x)?;
+                let colour = options.colours.colour(dot);
+                let centre = geometry.dot_centre((x as i32, y as i32), dot);
 
-                Circle::new((x_x as i32, y_y as i32), 5, color.filled())
+                Some(Circle::new(centre, options.dot_radius, colour.filled()))
             })
         })
-    ).unwrap();
+    )?;
 
+    root_area.present()?;
     Ok(())
 }
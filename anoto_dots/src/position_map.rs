@@ -0,0 +1,96 @@
+use ndarray::Array2;
+
+// Absolute position of every dot of a bit-matrix, as decoded by the windows
+// covering it. A dot is inconsistent if those windows disagree on where it
+// is, or if its position is not one step away from its neighbours'.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PositionMap {
+    pub positions: Array2<Option<(i32, i32)>>,
+    pub inconsistent: Array2<bool>,
+}
+
+impl PositionMap {
+    // Build the map from the positions decoded for every order x order window,
+    // indexed by the window's top-left dot
+    pub(crate) fn from_windows(windows: &Array2<Option<(i32, i32)>>, shape: (usize, usize), order: usize) -> Self {
+        let (rows, cols) = shape;
+        let mut positions = Array2::from_elem(shape, None);
+        let mut inconsistent = Array2::from_elem(shape, false);
+
+        // Every window covering a dot votes for the position of the matrix
+        // origin it implies, and the majority decides
+        for y in 0..rows {
+            for x in 0..cols {
+                let votes: Vec<(i32, i32)> = covering(y, windows.nrows(), order)
+                    .flat_map(|wy| covering(x, windows.ncols(), order).map(move |wx| (wy, wx)))
+                    .filter_map(|(wy, wx)| windows[[wy, wx]].map(|p| (p.0 - wx as i32, p.1 - wy as i32)))
+                    .collect();
+
+                let Some(origin) = majority(&votes) else {
+                    inconsistent[[y, x]] = !votes.is_empty();
+                    continue;
+                };
+                positions[[y, x]] = Some((origin.0 + x as i32, origin.1 + y as i32));
+                inconsistent[[y, x]] = votes.iter().any(|&v| v != origin);
+            }
+        }
+
+        // Decoded neighbours must be exactly one step apart
+        for y in 0..rows {
+            for x in 0..cols {
+                let Some(p) = positions[[y, x]] else { continue };
+                for (ny, nx, step) in [(y, x + 1, (1, 0)), (y + 1, x, (0, 1))] {
+                    if ny >= rows || nx >= cols {
+                        continue;
+                    }
+                    if let Some(q) = positions[[ny, nx]]
+                        && q != (p.0 + step.0, p.1 + step.1)
+                    {
+                        inconsistent[[y, x]] = true;
+                        inconsistent[[ny, nx]] = true;
+                    }
+                }
+            }
+        }
+
+        PositionMap { positions, inconsistent }
+    }
+
+    // Position of the dot in row y and column x, None if no window covering
+    // it decoded or they had no majority
+    pub fn position(&self, y: usize, x: usize) -> Option<(i32, i32)> {
+        self.positions[[y, x]]
+    }
+
+    pub fn is_consistent(&self, y: usize, x: usize) -> bool {
+        !self.inconsistent[[y, x]]
+    }
+
+    // (row, column) of every inconsistent dot
+    pub fn inconsistent_cells(&self) -> Vec<(usize, usize)> {
+        self.inconsistent.indexed_iter()
+            .filter(|&(_, &flag)| flag)
+            .map(|(cell, _)| cell)
+            .collect()
+    }
+}
+
+// Top-left indices along one axis of the windows covering index i
+fn covering(i: usize, windows: usize, order: usize) -> std::ops::Range<usize> {
+    (i + 1).saturating_sub(order)..(i + 1).min(windows)
+}
+
+// The value held by more than half of the votes
+fn majority(votes: &[(i32, i32)]) -> Option<(i32, i32)> {
+    let mut candidate = None;
+    let mut count = 0;
+    for &v in votes {
+        if count == 0 {
+            candidate = Some(v);
+        }
+        count = if candidate == Some(v) { count + 1 } else { count - 1 };
+    }
+
+    let candidate = candidate?;
+    (votes.iter().filter(|&&v| v == candidate).count() * 2 > votes.len()).then_some(candidate)
+}
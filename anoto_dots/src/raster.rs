@@ -0,0 +1,318 @@
+use crate::bitmatrix::BitMatrix;
+use crate::geometry::{GridGeometry, MM_PER_INCH};
+use crate::layout::PageLayout;
+use flate2::Compression;
+use flate2::Crc;
+use flate2::write::ZlibEncoder;
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+
+// Nominal dot diameter the default dot size is derived from
+const DOT_DIAMETER_MM: f64 = 0.1;
+
+// How render_raster places the dots. Positions are computed in millimetres
+// and converted to printer pixels at dpi, so that a page printed at the same
+// resolution needs no resampling.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RasterOptions {
+    pub geometry: GridGeometry,
+    pub dpi: u32,
+    // Width and height of a dot in pixels
+    pub dot_size_px: u32,
+    // Image size in pixels, by default just large enough for the grid
+    pub size_px: Option<(u32, u32)>,
+}
+
+impl Default for RasterOptions {
+    // Nominal grid at 600 DPI with the first dot half a pitch from the corner
+    fn default() -> Self {
+        let geometry = GridGeometry::default();
+        let half = geometry.pitch_mm / 2.0;
+        RasterOptions {
+            geometry: geometry.with_origin((half, half)),
+            dpi: 600,
+            dot_size_px: dot_size_px(600),
+            size_px: None,
+        }
+    }
+}
+
+impl RasterOptions {
+    // Full page of paper at the layout's resolution
+    pub fn for_page(layout: &PageLayout) -> Self {
+        RasterOptions {
+            geometry: layout.page_grid(),
+            dpi: layout.dpi,
+            dot_size_px: dot_size_px(layout.dpi),
+            size_px: Some(layout.pixel_size()),
+        }
+    }
+
+    // Length in millimetres to pixels
+    fn px(&self, mm: f64) -> f64 {
+        mm / MM_PER_INCH * self.dpi as f64
+    }
+}
+
+// Pixels covering the nominal dot diameter, at least one
+fn dot_size_px(dpi: u32) -> u32 {
+    ((DOT_DIAMETER_MM / MM_PER_INCH * dpi as f64).round() as u32).max(1)
+}
+
+// A 1-bit image, packed eight pixels per byte with the leftmost pixel in the
+// most significant bit. Set bits are black.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Raster {
+    width: u32,
+    height: u32,
+    dpi: u32,
+    data: Vec<u8>,
+}
+
+impl Raster {
+    // A white image
+    pub fn new(width: u32, height: u32, dpi: u32) -> Self {
+        Raster {
+            width,
+            height,
+            dpi,
+            data: vec![0; Self::stride_of(width) * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn dpi(&self) -> u32 {
+        self.dpi
+    }
+
+    fn stride_of(width: u32) -> usize {
+        width.div_ceil(8) as usize
+    }
+
+    fn stride(&self) -> usize {
+        Self::stride_of(self.width)
+    }
+
+    // Packed pixels of row y
+    pub fn row(&self, y: u32) -> &[u8] {
+        let start = y as usize * self.stride();
+        &self.data[start..start + self.stride()]
+    }
+
+    pub fn is_black(&self, x: u32, y: u32) -> bool {
+        self.row(y)[x as usize / 8] & (0x80 >> (x % 8)) != 0
+    }
+
+    pub fn set_black(&mut self, x: u32, y: u32) {
+        let stride = self.stride();
+        self.data[y as usize * stride + x as usize / 8] |= 0x80 >> (x % 8);
+    }
+
+    pub fn black_pixels(&self) -> usize {
+        self.data.iter().map(|b| b.count_ones() as usize).sum()
+    }
+
+    // Grayscale PNG with one bit per pixel and the resolution recorded in
+    // the pHYs chunk. PNG grayscale has black at zero, so the bits are
+    // inverted.
+    pub fn to_png(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let rows = (0..self.height).map(|y| self.row(y).iter().map(|b| !b).collect());
+        encode_png((self.width, self.height), self.dpi, PngColour::Bilevel, rows)
+    }
+
+    // Baseline bilevel TIFF in a single PackBits strip
+    pub fn to_tiff(&self) -> Vec<u8> {
+        let mut strip = Vec::new();
+        for y in 0..self.height {
+            packbits(self.row(y), &mut strip);
+        }
+
+        // Header, strip, resolution rationals, then the directory
+        let mut out: Vec<u8> = b"II*\0".to_vec();
+        let strip_offset = 8u32;
+        let resolution_offset = strip_offset + strip.len() as u32 + (strip.len() % 2) as u32;
+        let ifd_offset = resolution_offset + 8;
+        out.extend_from_slice(&ifd_offset.to_le_bytes());
+        out.extend_from_slice(&strip);
+        if strip.len() % 2 == 1 {
+            out.push(0);
+        }
+        out.extend_from_slice(&self.dpi.to_le_bytes());
+        out.extend_from_slice(&1u32.to_le_bytes());
+
+        // Tags in ascending order: (tag, type, value) with type 3 for SHORT,
+        // 4 for LONG and 5 for RATIONAL
+        let entries: [(u16, u16, u32); 12] = [
+            (256, 4, self.width),
+            (257, 4, self.height),
+            (258, 3, 1),
+            (259, 3, 32773),
+            (262, 3, 0),
+            (273, 4, strip_offset),
+            (277, 3, 1),
+            (278, 4, self.height),
+            (279, 4, strip.len() as u32),
+            (282, 5, resolution_offset),
+            (283, 5, resolution_offset),
+            (296, 3, 2),
+        ];
+        out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        for (tag, kind, value) in entries {
+            out.extend_from_slice(&tag.to_le_bytes());
+            out.extend_from_slice(&kind.to_le_bytes());
+            out.extend_from_slice(&1u32.to_le_bytes());
+            if kind == 3 {
+                out.extend_from_slice(&(value as u16).to_le_bytes());
+                out.extend_from_slice(&[0, 0]);
+            } else {
+                out.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out
+    }
+
+    pub fn save_png(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        let mut file = File::create(filename)?;
+        file.write_all(&self.to_png()?)?;
+        Ok(())
+    }
+
+    pub fn save_tiff(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        let mut file = File::create(filename)?;
+        file.write_all(&self.to_tiff())?;
+        Ok(())
+    }
+}
+
+// Pixel formats written by encode_png
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PngColour {
+    Bilevel,
+    Rgb,
+}
+
+// PNG of the given (width, height) from packed rows without filter bytes
+pub(crate) fn encode_png(
+    size: (u32, u32),
+    dpi: u32,
+    colour: PngColour,
+    rows: impl Iterator<Item = Vec<u8>>,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut out: Vec<u8> = b"\x89PNG\r\n\x1a\n".to_vec();
+
+    // Bit depth and colour type
+    let format: [u8; 2] = match colour {
+        PngColour::Bilevel => [1, 0],
+        PngColour::Rgb => [8, 2],
+    };
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&size.0.to_be_bytes());
+    header.extend_from_slice(&size.1.to_be_bytes());
+    header.extend_from_slice(&format);
+    header.extend_from_slice(&[0, 0, 0]);
+    png_chunk(&mut out, b"IHDR", &header);
+
+    let per_metre = (dpi as f64 / MM_PER_INCH * 1000.0).round() as u32;
+    let mut phys = Vec::with_capacity(9);
+    phys.extend_from_slice(&per_metre.to_be_bytes());
+    phys.extend_from_slice(&per_metre.to_be_bytes());
+    phys.push(1);
+    png_chunk(&mut out, b"pHYs", &phys);
+
+    // Every row starts with filter type 0
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    for row in rows {
+        encoder.write_all(&[0])?;
+        encoder.write_all(&row)?;
+    }
+    png_chunk(&mut out, b"IDAT", &encoder.finish()?);
+    png_chunk(&mut out, b"IEND", &[]);
+    Ok(out)
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    let mut crc = Crc::new();
+    crc.update(kind);
+    crc.update(data);
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    out.extend_from_slice(&crc.sum().to_be_bytes());
+}
+
+// PackBits encoding of one row: runs of up to 128 equal bytes become a
+// repeat count, everything else is copied literally
+fn packbits(row: &[u8], out: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < row.len() {
+        let run = row[i..].iter().take(128).take_while(|&&b| b == row[i]).count();
+        if run >= 2 {
+            out.push((1 - run as i32) as u8);
+            out.push(row[i]);
+            i += run;
+            continue;
+        }
+
+        // Literal bytes up to the next run of at least two
+        let start = i;
+        while i < row.len() && i - start < 128 && !(i + 1 < row.len() && row[i] == row[i + 1]) {
+            i += 1;
+        }
+        out.push((i - start - 1) as u8);
+        out.extend_from_slice(&row[start..i]);
+    }
+}
+
+// Render every dot as a square of dot_size_px pixels, with the corners
+// rounded off for larger dots, centred on its physical position snapped to
+// the pixel grid. Erased dots are left out.
+pub fn render_raster(bitmatrix: &BitMatrix, options: &RasterOptions) -> Raster {
+    let geometry = &options.geometry;
+    let (width, height) = options.size_px.unwrap_or_else(|| {
+        let (right, bottom) = geometry.grid_point((bitmatrix.cols() as i32, bitmatrix.rows() as i32));
+        let half = geometry.pitch_mm / 2.0;
+        (options.px(right - half).round() as u32, options.px(bottom - half).round() as u32)
+    });
+    let mut raster = Raster::new(width, height, options.dpi);
+
+    // Pixels of a dot relative to its top-left corner
+    let size = options.dot_size_px as i64;
+    let radius = size as f64 / 2.0;
+    let mask: Vec<(i64, i64)> = (0..size)
+        .flat_map(|j| (0..size).map(move |i| (i, j)))
+        .filter(|&(i, j)| {
+            let (dx, dy) = (i as f64 + 0.5 - radius, j as f64 + 0.5 - radius);
+            dx * dx + dy * dy <= radius * radius
+        })
+        .collect();
+
+    for y in 0..bitmatrix.rows() {
+        for x in 0..bitmatrix.cols() {
+            let Some(dot) = bitmatrix.dot(y, x) else { continue };
+            let (cx, cy) = geometry.dot_centre((x as i32, y as i32), dot);
+            let left = (options.px(cx) - radius).round() as i64;
+            let top = (options.px(cy) - radius).round() as i64;
+            for &(i, j) in &mask {
+                let (px, py) = (left + i, top + j);
+                if px >= 0 && py >= 0 && px < width as i64 && py < height as i64 {
+                    raster.set_black(px as u32, py as u32);
+                }
+            }
+        }
+    }
+    raster
+}
+
+// Render a single pattern page at the layout's resolution
+pub fn render_page(layout: &PageLayout, bitmatrix: &BitMatrix) -> Raster {
+    render_raster(bitmatrix, &RasterOptions::for_page(layout))
+}
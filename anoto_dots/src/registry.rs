@@ -0,0 +1,156 @@
+use crate::AnotoCodec;
+use crate::address::DotAddress;
+use crate::layout::{PageLayout, PatternRect};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use crate::persist::{read_json, write_json_atomic};
+
+// Reasons a page cannot be registered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    DuplicatePage { document_id: String, page_number: u32 },
+    Overlap { document_id: String, page_number: u32 },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegistryError::DuplicatePage { document_id, page_number } => {
+                write!(f, "Page {} of {} is already registered", page_number, document_id)
+            }
+            RegistryError::Overlap { document_id, page_number } => {
+                write!(f, "Pattern overlaps page {} of {}", page_number, document_id)
+            }
+        }
+    }
+}
+
+impl Error for RegistryError {}
+
+// A printed page and the pattern region it carries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegisteredPage {
+    pub document_id: String,
+    pub page_number: u32,
+    pub layout: PageLayout,
+    pub rect: PatternRect,
+}
+
+// Where a decoded dot lies: its page and the paper coordinates in
+// millimetres from the top-left corner of that page
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageHit<'a> {
+    pub document_id: &'a str,
+    pub page_number: u32,
+    pub local_mm: (f64, f64),
+}
+
+// Registry of printed pages that resolves decoded coordinates back to pages.
+// Pages are indexed by the left edge of their region and, within each left
+// edge, by the top edge. Since registered regions never overlap, only pages
+// starting at most one page size before a dot can contain it.
+#[derive(Debug, Clone, Default)]
+pub struct PageRegistry {
+    pages: Vec<RegisteredPage>,
+    index: BTreeMap<u64, BTreeMap<u64, usize>>,
+    max_width: u64,
+    max_height: u64,
+}
+
+impl PageRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pages(&self) -> &[RegisteredPage] {
+        &self.pages
+    }
+
+    // Register a page whose top-left dot is at origin, e.g. as handed out by
+    // PatternAllocator
+    pub fn register(
+        &mut self,
+        document_id: &str,
+        page_number: u32,
+        layout: PageLayout,
+        origin: DotAddress,
+    ) -> Result<&RegisteredPage, RegistryError> {
+        self.insert(RegisteredPage {
+            document_id: document_id.to_string(),
+            page_number,
+            layout,
+            rect: layout.pattern_rect(origin),
+        })
+    }
+
+    fn insert(&mut self, page: RegisteredPage) -> Result<&RegisteredPage, RegistryError> {
+        if self.pages.iter().any(|p| p.document_id == page.document_id && p.page_number == page.page_number) {
+            return Err(RegistryError::DuplicatePage {
+                document_id: page.document_id,
+                page_number: page.page_number,
+            });
+        }
+        if let Some(p) = self.pages.iter().find(|p| p.rect.overlaps(&page.rect)) {
+            return Err(RegistryError::Overlap {
+                document_id: p.document_id.clone(),
+                page_number: p.page_number,
+            });
+        }
+
+        let rect = page.rect;
+        self.max_width = self.max_width.max(rect.width);
+        self.max_height = self.max_height.max(rect.height);
+        self.index.entry(rect.origin.x).or_default().insert(rect.origin.y, self.pages.len());
+        self.pages.push(page);
+        Ok(&self.pages[self.pages.len() - 1])
+    }
+
+    // The registered page whose region contains a global address
+    pub fn find(&self, address: DotAddress) -> Option<&RegisteredPage> {
+        let x_from = (address.x + 1).saturating_sub(self.max_width);
+        let y_from = (address.y + 1).saturating_sub(self.max_height);
+
+        self.index.range(x_from..=address.x)
+            .flat_map(|(_, column)| column.range(y_from..=address.y))
+            .map(|(_, &i)| &self.pages[i])
+            .find(|p| p.rect.contains(address))
+    }
+
+    // Resolve a global address to its page and local paper coordinates
+    pub fn resolve_address(&self, address: DotAddress) -> Option<PageHit<'_>> {
+        let page = self.find(address)?;
+        let local = (
+            (address.x - page.rect.origin.x) as i32,
+            (address.y - page.rect.origin.y) as i32,
+        );
+        Some(PageHit {
+            document_id: &page.document_id,
+            page_number: page.page_number,
+            local_mm: page.layout.page_grid().grid_point(local),
+        })
+    }
+
+    // Resolve the section and position returned by decode_section and
+    // decode_position
+    pub fn resolve(&self, codec: &AnotoCodec, section: (i32, i32), position: (i32, i32)) -> Option<PageHit<'_>> {
+        self.resolve_address(codec.dot_address(section, position))
+    }
+
+    // Load a registry saved as JSON and rebuild its index
+    pub fn load(filename: &str) -> Result<PageRegistry, Box<dyn Error>> {
+        let pages: Vec<RegisteredPage> = read_json(filename)?;
+
+        let mut registry = PageRegistry::new();
+        for page in pages {
+            registry.insert(page)?;
+        }
+        Ok(registry)
+    }
+
+    // Save the registered pages as JSON, replacing the file atomically
+    pub fn save(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        write_json_atomic(filename, &self.pages)
+    }
+}
@@ -0,0 +1,40 @@
+use crate::DecodingError;
+use serde::Serialize;
+
+// Intermediate values of decoding one axis, filled in as far as decoding got.
+// Rows count along the bit plane of the axis, so for x they are the columns
+// of the window.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AxisReport {
+    // MNS locations matching each row, several if erased dots allow it
+    pub locations: Vec<Vec<usize>>,
+    // Difference between the locations of consecutive rows, None if unknown
+    pub deltas: Vec<Option<i32>>,
+    // Mixed-radix digits of each delta, one per SNS, ERASED if unknown
+    pub coefficients: Vec<Vec<i8>>,
+    // Position in each SNS, combined by the CRT
+    pub sns_positions: Vec<i64>,
+    pub position: Option<i32>,
+}
+
+// Everything decode_with_report learned about a window, including the step
+// that failed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DecodeReport {
+    pub shape: (usize, usize),
+    pub x: AxisReport,
+    pub y: AxisReport,
+    pub position: Option<(i32, i32)>,
+    pub section: Option<(i32, i32)>,
+    pub error: Option<DecodingError>,
+}
+
+impl DecodeReport {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
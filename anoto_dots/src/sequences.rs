@@ -0,0 +1,132 @@
+use crate::builder::gcd;
+use crate::spec::CodecSpec;
+
+// Upper bound on search steps spent looking for a single sequence
+const SEARCH_BUDGET: usize = 2_000_000;
+
+// Generate a spec for a codec reading mns_order x mns_order windows, with one
+// secondary sequence per prime factor. Like the Anoto patterns, the
+// MNS is a binary sequence of length 2^order - 1, the delta range is centred
+// in it, and the secondary sequences are as long as possible while keeping
+// their lengths pairwise coprime.
+pub fn generate_codec(mns_order: usize, pfactors: &[i32]) -> Option<CodecSpec> {
+    if mns_order < 2 || pfactors.iter().any(|&f| f < 2) {
+        return None;
+    }
+
+    let mns_length = 2usize.checked_pow(mns_order as u32)? - 1;
+    let mns = find_cyclic_sequence(2, mns_order, mns_length)?;
+
+    let span: usize = pfactors.iter().map(|&f| f as usize).product();
+    if span + 1 > mns_length {
+        return None;
+    }
+    let lo = (mns_length - span).div_ceil(2);
+    let delta_range = (lo as i32, (lo + span - 1) as i32);
+
+    let mut sns: Vec<Vec<i8>> = Vec::new();
+    for &factor in pfactors {
+        let max_length = (factor as usize).checked_pow(mns_order as u32 - 1)?;
+        let seq = (mns_order - 1..=max_length).rev()
+            .filter(|&len| sns.iter().all(|s| gcd(s.len(), len) == 1))
+            .find_map(|len| find_cyclic_sequence(factor as usize, mns_order - 1, len))?;
+        sns.push(seq);
+    }
+
+    Some(CodecSpec {
+        name: format!("generated_{}x{}", mns_order, mns_order),
+        mns,
+        mns_order,
+        sns,
+        pfactors: pfactors.to_vec(),
+        delta_range,
+    })
+}
+
+// Search a cyclic sequence of the given length over 0..alphabet in which no
+// window of length order occurs twice, i.e. a cycle in the de Bruijn graph.
+// Returns None if there is none or the search budget runs out.
+pub fn find_cyclic_sequence(alphabet: usize, order: usize, length: usize) -> Option<Vec<i8>> {
+    let windows = alphabet.checked_pow(order as u32)?;
+    if alphabet < 2 || alphabet > i8::MAX as usize || order == 0 || length < order || length > windows {
+        return None;
+    }
+    if length == windows {
+        return Some(de_bruijn(alphabet, order));
+    }
+
+    // Depth-first search over the next symbol, preferring large symbols as in
+    // the greedy construction of de Bruijn sequences. The sequence starts with
+    // order - 1 zeros and must end with them again to close the cycle, so the
+    // windows wrapping around are checked like all others.
+    let mut seq = vec![0usize; order - 1];
+    let mut used = vec![false; windows];
+    let mut below = alphabet;
+    let mut steps = 0;
+
+    loop {
+        steps += 1;
+        if steps > SEARCH_BUDGET {
+            return None;
+        }
+
+        if seq.len() == length + order - 1 {
+            seq.truncate(length);
+            return Some(seq.into_iter().map(|v| v as i8).collect());
+        }
+
+        // The closing symbols have to repeat the leading zeros
+        let limit = if seq.len() >= length { below.min(1) } else { below };
+        let mut placed = false;
+        for symbol in (0..limit).rev() {
+            seq.push(symbol);
+            let w = window_key(&seq[seq.len() - order..], alphabet);
+            if !used[w] {
+                used[w] = true;
+                placed = true;
+                break;
+            }
+            seq.pop();
+        }
+
+        if placed {
+            below = alphabet;
+        } else {
+            // Backtrack and try a smaller symbol one position earlier
+            if seq.len() == order - 1 {
+                return None;
+            }
+            let w = window_key(&seq[seq.len() - order..], alphabet);
+            used[w] = false;
+            below = seq.pop().unwrap_or(0);
+        }
+    }
+}
+
+// Lexicographically least de Bruijn sequence, built from Lyndon words
+pub fn de_bruijn(alphabet: usize, order: usize) -> Vec<i8> {
+    let mut seq = Vec::new();
+    let mut a = vec![0usize; alphabet * order + 1];
+
+    fn db(t: usize, p: usize, alphabet: usize, order: usize, a: &mut [usize], seq: &mut Vec<i8>) {
+        if t > order {
+            if order.is_multiple_of(p) {
+                seq.extend(a[1..=p].iter().map(|&v| v as i8));
+            }
+        } else {
+            a[t] = a[t - p];
+            db(t + 1, p, alphabet, order, a, seq);
+            for j in (a[t - p] + 1)..alphabet {
+                a[t] = j;
+                db(t + 1, t, alphabet, order, a, seq);
+            }
+        }
+    }
+
+    db(1, 1, alphabet, order, &mut a, &mut seq);
+    seq
+}
+
+fn window_key(window: &[usize], alphabet: usize) -> usize {
+    window.iter().fold(0, |key, &v| key * alphabet + v)
+}
@@ -0,0 +1,64 @@
+use crate::AnotoCodec;
+use crate::builder::{AnotoCodecBuilder, CodecError};
+use serde::{Serialize, Deserialize};
+use crate::persist::{read_json, write_atomic};
+use std::fs;
+use std::error::Error;
+use std::path::Path;
+
+// Complete description of a codec that can be shared as JSON or TOML
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodecSpec {
+    pub name: String,
+    pub mns: Vec<i8>,
+    pub mns_order: usize,
+    pub sns: Vec<Vec<i8>>,
+    pub pfactors: Vec<i32>,
+    pub delta_range: (i32, i32),
+}
+
+impl CodecSpec {
+    pub fn builder(&self) -> AnotoCodecBuilder {
+        AnotoCodecBuilder::new()
+            .mns(self.mns.clone(), self.mns_order)
+            .sns(self.sns.clone())
+            .pfactors(self.pfactors.clone())
+            .delta_range(self.delta_range)
+    }
+
+    // Validate the spec and build its codec
+    pub fn to_codec(&self) -> Result<AnotoCodec, CodecError> {
+        self.builder().build()
+    }
+
+    // Load a spec, as TOML if the file ends in .toml and as JSON otherwise
+    pub fn load(filename: &str) -> Result<CodecSpec, Box<dyn Error>> {
+        if is_toml(filename) {
+            Ok(toml::from_str(&fs::read_to_string(filename)?)?)
+        } else {
+            read_json(filename)
+        }
+    }
+
+    // Save the spec, as TOML if the file ends in .toml and as JSON otherwise
+    pub fn save(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        let content = if is_toml(filename) {
+            toml::to_string(self)?
+        } else {
+            serde_json::to_string_pretty(self)?
+        };
+        write_atomic(filename, content.as_bytes())
+    }
+}
+
+impl TryFrom<&CodecSpec> for AnotoCodec {
+    type Error = CodecError;
+
+    fn try_from(spec: &CodecSpec) -> Result<Self, Self::Error> {
+        spec.to_codec()
+    }
+}
+
+fn is_toml(filename: &str) -> bool {
+    Path::new(filename).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+}
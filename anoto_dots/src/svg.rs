@@ -0,0 +1,78 @@
+use crate::bitmatrix::BitMatrix;
+use crate::geometry::{GridGeometry, format_length as mm};
+use crate::layout::PageLayout;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::Write;
+
+// How render_svg places the dots. All lengths are in millimetres.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SvgOptions {
+    pub geometry: GridGeometry,
+    pub dot_diameter_mm: f64,
+    // Document size, by default just large enough for the grid
+    pub size_mm: Option<(f64, f64)>,
+}
+
+impl Default for SvgOptions {
+    // Nominal grid with the first dot half a pitch from the corner
+    fn default() -> Self {
+        let geometry = GridGeometry::default();
+        let half = geometry.pitch_mm / 2.0;
+        SvgOptions {
+            geometry: geometry.with_origin((half, half)),
+            dot_diameter_mm: 0.1,
+            size_mm: None,
+        }
+    }
+}
+
+impl SvgOptions {
+    // Full page of paper with the dots placed inside its margins
+    pub fn for_page(layout: &PageLayout) -> Self {
+        SvgOptions {
+            geometry: layout.page_grid(),
+            size_mm: Some(layout.paper.dimensions_mm()),
+            ..Self::default()
+        }
+    }
+}
+
+// Render every dot as a filled circle at its physical position. The document
+// is measured in millimetres and has no decoration, so that it prints at
+// native resolution. Erased dots are left out.
+pub fn render_svg(bitmatrix: &BitMatrix, options: &SvgOptions) -> String {
+    let geometry = &options.geometry;
+    let (width, height) = options.size_mm.unwrap_or_else(|| {
+        let (right, bottom) = geometry.grid_point((bitmatrix.cols() as i32, bitmatrix.rows() as i32));
+        (right - geometry.pitch_mm / 2.0, bottom - geometry.pitch_mm / 2.0)
+    });
+
+    let mut svg = String::new();
+    svg.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}mm\" height=\"{}mm\" viewBox=\"0 0 {} {}\">",
+        mm(width), mm(height), mm(width), mm(height)
+    );
+    svg.push_str("<g fill=\"#000000\" stroke=\"none\">\n");
+
+    let r = mm(options.dot_diameter_mm / 2.0);
+    for y in 0..bitmatrix.rows() {
+        for x in 0..bitmatrix.cols() {
+            let Some(dot) = bitmatrix.dot(y, x) else { continue };
+            let (cx, cy) = geometry.dot_centre((x as i32, y as i32), dot);
+            let _ = writeln!(svg, "<circle cx=\"{}\" cy=\"{}\" r=\"{}\"/>", mm(cx), mm(cy), r);
+        }
+    }
+
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
+
+pub fn save_svg(bitmatrix: &BitMatrix, options: &SvgOptions, filename: &str) -> Result<(), Box<dyn Error>> {
+    let mut file = File::create(filename)?;
+    file.write_all(render_svg(bitmatrix, options).as_bytes())?;
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use anoto_dots::address::DotAddress;
+use anoto_dots::anoto_6x6_a4_fixed;
+
+#[test]
+fn addresses_pack_section_and_position() {
+    let codec = anoto_6x6_a4_fixed();
+    let size = codec.section_size();
+    assert_eq!(size, 236 * 233 * 31 * 241);
+
+    let address = codec.dot_address((10, 2), (7, 3));
+    assert_eq!(address, DotAddress::new(10 * size + 7, 2 * size + 3));
+    assert_eq!(codec.address_parts(address), ((10, 2), (7, 3)));
+
+    let last = codec.dot_address((62, 62), (size as i32 - 1, size as i32 - 1));
+    assert_eq!(last, DotAddress::new(63 * size - 1, 63 * size - 1));
+    assert_eq!(codec.dot_address((-1, 63), (0, 0)), DotAddress::new(62 * size, 0));
+}
+
+#[test]
+fn encoded_address_regions_decode_to_their_address() {
+    let codec = anoto_6x6_a4_fixed();
+    let origin = codec.dot_address((33, 4), (25_000, 40_321));
+
+    let region = codec.encode_address_region(origin, (10, 12)).unwrap();
+    assert_eq!(region, codec.encode_region((25_000, 40_321), (10, 12), (33, 4)));
+
+    for (dx, dy) in [(0, 0), (6, 4), (3, 2)] {
+        let window = region.window((dx, dy), (6, 6));
+        assert_eq!(codec.decode_address(&window).unwrap(), origin.offset(dx as u64, dy as u64));
+    }
+}
+
+#[test]
+fn regions_may_not_cross_a_section_boundary() {
+    let codec = anoto_6x6_a4_fixed();
+    let size = codec.section_size();
+
+    assert!(codec.encode_address_region(DotAddress::new(size - 6, 0), (6, 6)).is_some());
+    assert!(codec.encode_address_region(DotAddress::new(size - 5, 0), (6, 6)).is_none());
+    assert!(codec.encode_address_region(DotAddress::new(0, 63 * size), (6, 6)).is_none());
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use anoto_dots::address::DotAddress;
+use anoto_dots::allocator::{AllocationError, PatternAllocator};
+use anoto_dots::anoto_6x6_a4_fixed;
+use anoto_dots::layout::{PageLayout, PatternRect};
+use common::temp_path;
+
+#[test]
+fn pages_get_distinct_regions() {
+    let codec = anoto_6x6_a4_fixed();
+    let mut allocator = PatternAllocator::new(&codec);
+    let shape = PageLayout::a4().shape();
+
+    let rects: Vec<PatternRect> = (0..50)
+        .map(|i| allocator.allocate(&format!("notebook-1/page-{}", i), shape).unwrap())
+        .collect();
+
+    assert_eq!(rects[0].origin, DotAddress::new(0, 0));
+    assert_eq!(rects[1].origin, DotAddress::new(633, 0));
+    for (i, a) in rects.iter().enumerate() {
+        assert_eq!(a.shape(), shape);
+        assert!(rects[i + 1..].iter().all(|b| !a.overlaps(b)));
+    }
+    assert_eq!(allocator.get("notebook-1/page-7"), Some(&rects[7]));
+}
+
+#[test]
+fn duplicates_and_overlaps_are_refused() {
+    let codec = anoto_6x6_a4_fixed();
+    let mut allocator = PatternAllocator::new(&codec);
+    let first = allocator.allocate("a", (100, 100)).unwrap();
+
+    assert_eq!(
+        allocator.allocate("a", (100, 100)),
+        Err(AllocationError::DuplicateId { id: "a".to_string() })
+    );
+    let overlapping = PatternRect { origin: DotAddress::new(50, 50), width: 100, height: 100 };
+    assert_eq!(
+        allocator.reserve("b", overlapping),
+        Err(AllocationError::Overlap { id: "b".to_string(), existing: "a".to_string() })
+    );
+    let crossing = PatternRect { origin: DotAddress::new(codec.section_size() - 10, 0), width: 20, height: 20 };
+    assert!(matches!(allocator.reserve("c", crossing), Err(AllocationError::OutsidePatternSpace { .. })));
+    assert_eq!(allocator.allocate("d", (0, 10)), Err(AllocationError::EmptyRegion));
+
+    // Allocation continues around explicitly reserved regions
+    let reserved = PatternRect { origin: DotAddress::new(100, 0), width: 30, height: 30 };
+    allocator.reserve("e", reserved).unwrap();
+    let next = allocator.allocate("f", (100, 100)).unwrap();
+    assert_eq!(next.origin, DotAddress::new(130, 0));
+    assert!(!next.overlaps(&first) && !next.overlaps(&reserved));
+}
+
+#[test]
+fn full_sections_move_on_to_the_next_section() {
+    let codec = anoto_6x6_a4_fixed();
+    let size = codec.section_size();
+    let mut allocator = PatternAllocator::new(&codec);
+
+    let huge = (size as usize, size as usize - 1);
+    assert_eq!(allocator.allocate("s0", huge).unwrap().origin, DotAddress::new(0, 0));
+    assert_eq!(allocator.allocate("s1", huge).unwrap().origin, DotAddress::new(size, 0));
+    assert_eq!(allocator.allocate("s2", (10, 10)).unwrap().origin, DotAddress::new(2 * size, 0));
+}
+
+#[test]
+fn allocation_table_survives_a_restart() {
+    let codec = anoto_6x6_a4_fixed();
+    let path = temp_path("allocations.json");
+    let _ = std::fs::remove_file(&path);
+
+    let mut allocator = PatternAllocator::open(&path, &codec).unwrap();
+    let first = allocator.allocate("page-1", (923, 633)).unwrap();
+    allocator.save(&path).unwrap();
+
+    let mut reopened = PatternAllocator::open(&path, &codec).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(reopened, allocator);
+    assert!(reopened.allocate("page-1", (923, 633)).is_err());
+    let second = reopened.allocate("page-2", (923, 633)).unwrap();
+    assert!(!second.overlaps(&first));
+}
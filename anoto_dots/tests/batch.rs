@@ -0,0 +1,47 @@
+use anoto_dots::bitmatrix::BitMatrix;
+use anoto_dots::{AnotoCodec, Decoded, anoto_6x6_a4_fixed};
+use std::thread;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn codec_is_send_and_sync() {
+    assert_send_sync::<AnotoCodec>();
+    assert_send_sync::<BitMatrix>();
+}
+
+#[test]
+fn batch_results_follow_the_window_order() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((60, 60), (10, 2));
+    let origins: Vec<(usize, usize)> = (0..500).map(|i| ((i * 7) % 54, (i * 13) % 54)).collect();
+    let mut windows: Vec<BitMatrix> = origins.iter().map(|&o| bits.window(o, (6, 6))).collect();
+    windows.push(bits.window((0, 0), (5, 6)));
+
+    let results = codec.decode_batch(&windows);
+    assert_eq!(results.len(), windows.len());
+    for (result, &(x, y)) in results.iter().zip(&origins) {
+        let expected = Decoded { position: (x as i32, y as i32), section: (10, 2), rotation: 0 };
+        assert_eq!(result.as_ref().unwrap(), &expected);
+    }
+    assert!(results.last().unwrap().is_err());
+}
+
+#[test]
+fn one_codec_serves_many_threads() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((40, 40), (3, 4));
+
+    thread::scope(|scope| {
+        for t in 0..4 {
+            let (codec, bits) = (&codec, &bits);
+            scope.spawn(move || {
+                for i in 0..30 {
+                    let (x, y) = ((t * 5 + i) % 34, (t * 3 + i * 2) % 34);
+                    let pos = codec.decode_position(&bits.window((x, y), (6, 6))).unwrap();
+                    assert_eq!(pos, (x as i32, y as i32));
+                }
+            });
+        }
+    });
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use anoto_dots::bitmatrix::{BitMatrix, BitMatrixError, Dot};
+use anoto_dots::persist::{load_bitmatrix_json, save_bitmatrix_json};
+use anoto_dots::{ERASED, anoto_6x6_a4_fixed, rot90};
+use common::temp_path;
+use ndarray::{Array2, Array3};
+
+#[test]
+fn construction_rejects_invalid_matrices() {
+    let err = BitMatrix::new(Array3::zeros((4, 4, 3))).unwrap_err();
+    assert_eq!(err, BitMatrixError::WrongChannels { channels: 3 });
+
+    let mut bits = Array3::zeros((4, 4, 2));
+    bits[[2, 1, 1]] = 2;
+    let err = BitMatrix::try_from(bits.clone()).unwrap_err();
+    assert_eq!(err, BitMatrixError::InvalidValue { y: 2, x: 1, bit: 1, value: 2 });
+
+    bits[[2, 1, 1]] = ERASED;
+    let matrix = BitMatrix::try_from(bits.clone()).unwrap();
+    assert!(matrix.has_erasures());
+    assert_eq!(matrix.dot(2, 1), None);
+    assert_eq!(Array3::from(matrix), bits);
+}
+
+#[test]
+fn dots_map_to_bits_and_back() {
+    for dot in [Dot::Up, Dot::Right, Dot::Left, Dot::Down] {
+        let (x_bit, y_bit) = dot.bits();
+        assert_eq!(Dot::from_bits(x_bit, y_bit), Some(dot));
+        assert_eq!(dot.rotate_ccw().rotate_ccw(), Dot::from_bits(1 - x_bit, 1 - y_bit).unwrap());
+    }
+
+    // The dot value x_bit + (y_bit << 1) counts Up, Right, Left, Down
+    for (value, dot) in [Dot::Up, Dot::Right, Dot::Left, Dot::Down].into_iter().enumerate() {
+        assert_eq!(Dot::from_bits((value & 1) as i8, (value >> 1) as i8), Some(dot));
+    }
+
+    let dots = Array2::from_shape_vec((1, 3), vec![Some(Dot::Right), None, Some(Dot::Down)]).unwrap();
+    let matrix = BitMatrix::from_dots(&dots);
+    assert_eq!(matrix.shape(), (1, 3));
+    assert_eq!((matrix.x_bit(0, 0), matrix.y_bit(0, 0)), (1, 0));
+    assert_eq!(matrix.dot(0, 1), None);
+    assert_eq!(matrix.dot(0, 2), Some(Dot::Down));
+}
+
+#[test]
+fn rot90_turns_positions_and_dots_counter_clockwise() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((3, 5), (10, 2));
+    let turned = rot90(&bits, 1);
+
+    assert_eq!(turned.shape(), (5, 3));
+    for y in 0..3 {
+        for x in 0..5 {
+            let expected = bits.dot(y, x).map(Dot::rotate_ccw);
+            assert_eq!(turned.dot(4 - x, y), expected);
+        }
+    }
+    assert_eq!(rot90(&turned, 3), bits);
+}
+
+#[test]
+fn turned_windows_decode_with_their_rotation() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((40, 40), (10, 2));
+
+    for (x, y) in [(0, 0), (13, 5), (30, 31)] {
+        let window = bits.window((x, y), (8, 8));
+        for k in 0..4 {
+            let decoded = codec.decode(&rot90(&window, k)).unwrap();
+            assert_eq!(decoded.position, (x as i32, y as i32));
+            assert_eq!(decoded.section, (10, 2));
+            assert_eq!(decoded.rotation, (4 - k) % 4);
+        }
+    }
+}
+
+#[test]
+fn bitmatrix_roundtrips_through_json() {
+    let mut matrix = anoto_6x6_a4_fixed().encode_bitmatrix((9, 16), (10, 2));
+    matrix.set_dot(4, 7, None);
+
+    let path = temp_path("bitmatrix.json");
+    save_bitmatrix_json(&matrix, &path).unwrap();
+    let loaded = load_bitmatrix_json(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded, matrix);
+}
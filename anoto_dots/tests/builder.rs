@@ -0,0 +1,81 @@
+use anoto_dots::AnotoCodec;
+use anoto_dots::builder::{AnotoCodecBuilder, CodecError};
+
+// A tiny but complete codec: the MNS holds every 3-bit window but 000 once,
+// the SNS are de Bruijn sequences over their factor's alphabet
+fn toy_builder() -> AnotoCodecBuilder {
+    AnotoCodec::builder()
+        .mns(vec![0, 0, 1, 0, 1, 1, 1], 3)
+        .sns(vec![vec![0, 0, 1, 1], vec![0, 0, 1, 1, 2, 2, 0, 2, 1]])
+        .pfactors(vec![2, 3])
+        .delta_range((1, 6))
+}
+
+#[test]
+fn valid_parameters_build_a_working_codec() {
+    let codec = toy_builder().build().unwrap();
+    codec.verify_roundtrip((12, 12), (3, 5)).unwrap();
+}
+
+#[test]
+fn rejects_repeated_mns_window() {
+    let err = toy_builder().mns(vec![0, 0, 1, 0, 0, 1, 1], 3).validate().unwrap_err();
+    assert_eq!(err, CodecError::MnsWindowRepeated { first: 0, repeat: 3 });
+}
+
+#[test]
+fn rejects_short_or_non_binary_mns() {
+    let err = toy_builder().mns(vec![0, 1], 3).validate().unwrap_err();
+    assert_eq!(err, CodecError::MnsTooShort { length: 2, order: 3 });
+
+    let err = toy_builder().mns(vec![0, 0, 1, 0, 2, 1, 1], 3).validate().unwrap_err();
+    assert_eq!(err, CodecError::MnsNotBinary { position: 4, value: 2 });
+
+    let err = toy_builder().mns(vec![0, 1], 1).validate().unwrap_err();
+    assert_eq!(err, CodecError::OrderTooSmall { order: 1 });
+}
+
+#[test]
+fn rejects_repeated_sns_window() {
+    let err = toy_builder()
+        .sns(vec![vec![0, 0, 1, 1], vec![0, 0, 1, 0, 0, 2, 0, 2, 1]])
+        .validate()
+        .unwrap_err();
+    assert_eq!(err, CodecError::SnsWindowRepeated { sequence: 1, first: 0, repeat: 3 });
+}
+
+#[test]
+fn rejects_sns_values_outside_their_factor() {
+    let err = toy_builder()
+        .sns(vec![vec![0, 0, 2, 1], vec![0, 0, 1, 1, 2, 2, 0, 2, 1]])
+        .validate()
+        .unwrap_err();
+    assert_eq!(err, CodecError::SnsValueOutOfRange { sequence: 0, position: 2, value: 2, factor: 2 });
+}
+
+#[test]
+fn rejects_sns_lengths_sharing_a_factor() {
+    let err = toy_builder()
+        .sns(vec![vec![0, 0, 1, 1], vec![0, 1, 1, 2, 2, 0, 2, 1]])
+        .validate()
+        .unwrap_err();
+    assert_eq!(err, CodecError::SnsLengthsNotCoprime { first: 0, second: 1, gcd: 4 });
+}
+
+#[test]
+fn rejects_factor_product_not_matching_delta_span() {
+    let err = toy_builder().delta_range((1, 5)).validate().unwrap_err();
+    assert_eq!(err, CodecError::FactorProductMismatch { product: 6, span: 5 });
+
+    let err = toy_builder().delta_range((0, 5)).validate().unwrap_err();
+    assert!(matches!(err, CodecError::InvalidDeltaRange { .. }));
+
+    let err = toy_builder().pfactors(vec![2, 3, 2]).validate().unwrap_err();
+    assert_eq!(err, CodecError::FactorCountMismatch { sequences: 2, factors: 3 });
+}
+
+#[test]
+fn errors_describe_the_violation() {
+    let err = toy_builder().delta_range((1, 5)).validate().unwrap_err();
+    assert_eq!(err.to_string(), "Prime factor product 6 does not match the delta range span 5");
+}
@@ -0,0 +1,17 @@
+// Helpers shared by the integration tests, not every test uses all of them
+#![allow(dead_code)]
+
+use std::env;
+
+// Path in the temporary directory, unique to this test process
+pub fn temp_path(name: &str) -> String {
+    env::temp_dir()
+        .join(format!("anoto_dots_{}_{}", std::process::id(), name))
+        .to_string_lossy()
+        .into_owned()
+}
+
+// Position of the first occurrence of needle at or after from
+pub fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack[from..].windows(needle.len()).position(|w| w == needle).map(|i| i + from)
+}
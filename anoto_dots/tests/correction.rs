@@ -0,0 +1,136 @@
+use anoto_dots::bitmatrix::BitMatrix;
+use anoto_dots::{DecodingError, anoto_6x6_a4_fixed};
+
+// Window of the pattern at origin with the given bits inverted, as
+// (row, column, bit) triples
+fn flipped(bits: &BitMatrix, origin: (usize, usize), size: usize, flips: &[(usize, usize, usize)]) -> BitMatrix {
+    let mut window = bits.window(origin, (size, size)).into_array();
+    for &(r, c, b) in flips {
+        window[[r, c, b]] = 1 - window[[r, c, b]];
+    }
+    BitMatrix::new(window).unwrap()
+}
+
+#[test]
+fn a_flipped_bit_is_corrected_or_rejected() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((60, 60), (10, 2));
+    let (mut corrected, mut rejected) = (0, 0);
+
+    for (x, y) in [(0, 0), (5, 10), (40, 40), (13, 47)] {
+        for i in 0..8 * 8 * 2 {
+            let window = flipped(&bits, (x, y), 8, &[(i / 16, i / 2 % 8, i % 2)]);
+            match codec.decode_position_corrected(&window, 1) {
+                Ok(result) => {
+                    assert_eq!(result, ((x as i32, y as i32), 1));
+                    corrected += 1;
+                }
+                Err(e) => {
+                    assert_eq!(e, DecodingError::CorrectionAmbiguous);
+                    rejected += 1;
+                }
+            }
+        }
+    }
+    assert!(corrected > 9 * rejected, "{} corrected, {} rejected", corrected, rejected);
+}
+
+#[test]
+fn two_flipped_bits_are_corrected_in_larger_windows() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((60, 60), (10, 2));
+
+    let window = flipped(&bits, (20, 4), 10, &[(1, 3, 0), (6, 8, 1)]);
+    assert_eq!(codec.decode_position_corrected(&window, 2).unwrap(), ((20, 4), 2));
+
+    let window = flipped(&bits, (20, 4), 10, &[(2, 2, 0), (7, 5, 0)]);
+    assert_eq!(codec.decode_position_corrected(&window, 2).unwrap(), ((20, 4), 2));
+}
+
+#[test]
+fn clean_windows_need_no_corrections() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((60, 60), (10, 2));
+
+    for (x, y) in [(0, 0), (33, 17)] {
+        let window = bits.window((x, y), (6, 6));
+        assert_eq!(codec.decode_position_corrected(&window, 0).unwrap(), ((x as i32, y as i32), 0));
+        let window = bits.window((x, y), (8, 8));
+        assert_eq!(codec.decode_position_corrected(&window, 1).unwrap(), ((x as i32, y as i32), 0));
+    }
+}
+
+#[test]
+fn budgets_beyond_the_window_size_are_limited() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((60, 60), (10, 2));
+
+    for (x, y) in [(0, 0), (5, 10), (33, 17), (48, 50)] {
+        for (size, max_corrections) in [(6, 2), (8, 2), (8, 4), (10, 3), (10, 6)] {
+            let window = bits.window((x, y), (size, size));
+            assert_eq!(
+                codec.decode_position_corrected(&window, max_corrections).unwrap(),
+                ((x as i32, y as i32), 0)
+            );
+        }
+    }
+}
+
+#[test]
+fn two_flipped_bits_are_beyond_small_windows() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((60, 60), (10, 2));
+    let mut rejected = 0;
+
+    // Pairs of flips a few dots apart, on the same or on different axes
+    for (x, y) in [(0, 0), (5, 10), (40, 40)] {
+        for i in 0..8 * 8 {
+            let flips = [(i / 8, i % 8, i % 2), ((i / 8 + 3) % 8, (i + 5) % 8, i / 3 % 2)];
+            let window = flipped(&bits, (x, y), 8, &flips);
+            for max_corrections in [2, 3] {
+                match codec.decode_position_corrected(&window, max_corrections) {
+                    // Only windows that flipped into a valid window elsewhere
+                    // decode, exactly like they do without correction
+                    Ok((pos, corrections)) => {
+                        assert_eq!(corrections, 0);
+                        assert_eq!(codec.decode_position(&window), Ok(pos));
+                    }
+                    Err(_) => rejected += 1,
+                }
+            }
+        }
+    }
+    assert!(rejected > 3 * 8 * 8 * 2 * 9 / 10);
+}
+
+#[test]
+fn minimal_windows_cannot_be_corrected() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((60, 60), (10, 2));
+
+    // Any 6x6 window is one flip away from another valid window, so a 6x6
+    // window is decoded as it is
+    let window = bits.window((5, 10), (6, 6));
+    assert_eq!(codec.decode_position_corrected(&window, 1).unwrap(), ((5, 10), 0));
+
+    for i in 0..6 * 6 * 2 {
+        let window = flipped(&bits, (5, 10), 6, &[(i / 12, i / 2 % 6, i % 2)]);
+        if let Ok((pos, corrections)) = codec.decode_position_corrected(&window, 1) {
+            assert_eq!(corrections, 0);
+            assert_eq!(codec.decode_position(&window), Ok(pos));
+        }
+    }
+}
+
+#[test]
+fn too_many_flipped_bits_are_rejected() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((60, 60), (10, 2));
+
+    let window = flipped(&bits, (13, 47), 8, &[(0, 1, 0), (3, 4, 0), (6, 2, 0)]);
+    assert!(codec.decode_position_corrected(&window, 1).is_err());
+    assert!(codec.decode_position_corrected(&window, 3).is_err());
+
+    let window = flipped(&bits, (13, 47), 10, &[(0, 1, 0), (3, 4, 0), (6, 2, 1)]);
+    assert!(codec.decode_position_corrected(&window, 3).is_err());
+}
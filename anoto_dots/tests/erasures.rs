@@ -0,0 +1,89 @@
+use anoto_dots::bitmatrix::BitMatrix;
+use anoto_dots::{DecodingError, ERASED, anoto_6x6_a4_fixed};
+
+#[test]
+fn erased_dots_match_anything_in_larger_windows() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((40, 40), (10, 2));
+
+    for (x, y) in [(0, 0), (7, 3), (25, 31)] {
+        let mut window = bits.window((x, y), (8, 8));
+        for (r, c) in [(0, 0), (3, 5), (7, 2)] {
+            window.set_dot(r, c, None);
+            let pos = codec.decode_position(&window).unwrap();
+            assert_eq!(pos, (x as i32, y as i32));
+            assert_eq!(codec.decode_section(&window, pos).unwrap(), (10, 2));
+        }
+    }
+}
+
+#[test]
+fn a_single_erased_bit_keeps_the_other_bit() {
+    let codec = anoto_6x6_a4_fixed();
+    let mut bits = codec.encode_region((100, 200), (8, 8), (4, 5)).into_array();
+    bits[[2, 3, 0]] = ERASED;
+    bits[[5, 1, 1]] = ERASED;
+
+    let window = BitMatrix::new(bits).unwrap();
+    assert_eq!(window.dot(2, 3), None);
+    assert_eq!(window.y_bit(2, 3), codec.encode_region((100, 200), (8, 8), (4, 5)).y_bit(2, 3));
+    assert_eq!(codec.decode_position(&window).unwrap(), (100, 200));
+}
+
+#[test]
+fn minimal_windows_with_erasures_decode_uniquely_or_not_at_all() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((30, 30), (10, 2));
+    let (mut unique, mut ambiguous) = (0, 0);
+
+    for (x, y) in [(0, 0), (5, 10), (20, 4), (24, 24)] {
+        for dot in 0..36 {
+            let mut window = bits.window((x, y), (6, 6));
+            window.set_dot(dot / 6, dot % 6, None);
+            match codec.decode_position(&window) {
+                Ok(pos) => {
+                    assert_eq!(pos, (x as i32, y as i32));
+                    unique += 1;
+                }
+                Err(e) => {
+                    assert!(e.is_ambiguous(), "unexpected {:?}", e);
+                    ambiguous += 1;
+                }
+            }
+        }
+    }
+    assert!(unique > 0 && ambiguous > 0);
+}
+
+#[test]
+fn too_many_erasures_are_ambiguous() {
+    let codec = anoto_6x6_a4_fixed();
+    let mut window = codec.encode_bitmatrix((6, 6), (10, 2));
+    for x in 0..6 {
+        window.set_dot(2, x, None);
+    }
+
+    let err = codec.decode_position(&window).unwrap_err();
+    assert_eq!(err, DecodingError::Ambiguous);
+    assert_eq!(err.to_string(), "Decoding error: Erased dots leave the match ambiguous");
+}
+
+#[test]
+fn sections_are_read_from_the_first_known_column_and_row() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((40, 40), (10, 2));
+
+    // Half of the first column and row is erased, too little is left of them
+    // to locate them in the MNS
+    for (x, y) in [(0, 0), (7, 3), (25, 31)] {
+        let mut window = bits.window((x, y), (8, 8));
+        for i in 0..4 {
+            window.set_dot(i, 0, None);
+            window.set_dot(0, i, None);
+        }
+        let pos = codec.decode_position(&window).unwrap();
+        assert_eq!(pos, (x as i32, y as i32));
+        assert_eq!(codec.decode_section(&window, pos).unwrap(), (10, 2));
+        assert_eq!(codec.decode_batch(&[window])[0].as_ref().unwrap().section, (10, 2));
+    }
+}
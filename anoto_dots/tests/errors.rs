@@ -0,0 +1,67 @@
+use anoto_dots::bitmatrix::BitMatrix;
+use anoto_dots::{AnotoCodec, Coordinate, DecodingError, anoto_6x6_a4_fixed};
+
+// Top-left 6x6 window of section (10, 2) with the given bits flipped
+fn corrupted_window(flips: &[[usize; 3]]) -> BitMatrix {
+    let codec = anoto_6x6_a4_fixed();
+    let mut bits = codec.encode_bitmatrix((6, 6), (10, 2)).into_array();
+    for &index in flips {
+        bits[index] = 1 - bits[index];
+    }
+    BitMatrix::new(bits).unwrap()
+}
+
+#[test]
+fn small_windows_report_a_shape_mismatch() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((5, 8), (10, 2));
+
+    let err = codec.decode_position(&bits).unwrap_err();
+    assert_eq!(err, DecodingError::ShapeMismatch { shape: (5, 8), order: 6 });
+    assert_eq!(codec.decode_section(&bits, (0, 0)).unwrap_err(), err);
+}
+
+#[test]
+fn unknown_column_reports_an_mns_miss() {
+    let codec = anoto_6x6_a4_fixed();
+    let mut bits = codec.encode_bitmatrix((6, 6), (10, 2)).into_array();
+    for y in 0..6 {
+        bits[[y, 2, 0]] = 1;
+    }
+
+    let err = codec.decode_position(&BitMatrix::new(bits).unwrap()).unwrap_err();
+    assert_eq!(err, DecodingError::MnsMiss { axis: Coordinate::X, row: 2 });
+    assert_eq!(err.to_string(), "Decoding error: Failed to find partial sequence in MNS (x, row 2)");
+}
+
+#[test]
+fn misread_dots_report_delta_and_sns_failures() {
+    let codec = anoto_6x6_a4_fixed();
+
+    let err = codec.decode_position(&corrupted_window(&[[0, 3, 0]])).unwrap_err();
+    assert_eq!(err, DecodingError::DeltaOutOfRange { axis: Coordinate::X, row: 3, value: 1 });
+
+    let err = codec.decode_position(&corrupted_window(&[[2, 1, 1]])).unwrap_err();
+    assert_eq!(err, DecodingError::DeltaOutOfRange { axis: Coordinate::Y, row: 3, value: 2 });
+
+    let err = codec.decode_position(&corrupted_window(&[[2, 5, 0]])).unwrap_err();
+    assert_eq!(err, DecodingError::SnsMiss { axis: Coordinate::X, sequence: 3 });
+    assert!(!err.is_ambiguous());
+}
+
+#[test]
+fn non_coprime_sequences_report_a_crt_failure() {
+    // Skips the builder, which would reject SNS lengths 4 and 8
+    let codec = AnotoCodec::new(
+        vec![0, 0, 1, 0, 1, 1, 1],
+        3,
+        vec![vec![0, 0, 1, 1], vec![0, 1, 1, 2, 2, 0, 2, 1]],
+        vec![2, 3],
+        (1, 6),
+    );
+    let bits = codec.encode_bitmatrix((3, 3), (0, 0));
+
+    let err = codec.decode_position(&bits).unwrap_err();
+    assert_eq!(err, DecodingError::CrtFailure { remainders: vec![0, 0], moduli: vec![4, 8] });
+    assert!(err.to_string().starts_with("Decoding error: Modular inverse does not exist"));
+}
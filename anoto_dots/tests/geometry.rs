@@ -0,0 +1,44 @@
+use anoto_dots::anoto_6x6_a4_fixed;
+use anoto_dots::bitmatrix::Dot;
+use anoto_dots::geometry::{GridGeometry, Unit};
+
+fn assert_close(a: (f64, f64), b: (f64, f64)) {
+    assert!((a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn nominal_grid_has_a_third_of_a_millimetre_pitch() {
+    let geometry = GridGeometry::default();
+    assert_close(geometry.grid_point((10, 20)), (3.0, 6.0));
+    assert_close(geometry.grid_point_in((254, 0), Unit::Inch), (3.0, 0.0));
+    assert_close(geometry.grid_point_in((0, 254), Unit::Point), (0.0, 216.0));
+
+    assert_close(geometry.dot_centre((10, 20), Dot::Up), (3.0, 5.95));
+    assert_close(geometry.dot_centre((10, 20), Dot::Left), (2.95, 6.0));
+    assert_close(geometry.dot_centre((10, 20), Dot::Right), (3.05, 6.0));
+    assert_close(geometry.dot_centre((10, 20), Dot::Down), (3.0, 6.05));
+}
+
+#[test]
+fn paper_coordinates_convert_back_to_positions() {
+    let geometry = GridGeometry::new(0.3, 1.0 / 6.0, (12.0, -4.5));
+    for position in [(0, 0), (-7, 3), (1234, 567)] {
+        assert_eq!(geometry.position_at(geometry.grid_point(position)), position);
+        for dot in [Dot::Up, Dot::Left, Dot::Right, Dot::Down] {
+            for unit in [Unit::Millimetre, Unit::Inch, Unit::Point] {
+                let centre = geometry.dot_centre_in(position, dot, unit);
+                assert_eq!(geometry.position_at_in(centre, unit), position);
+            }
+        }
+    }
+}
+
+#[test]
+fn decoded_positions_map_to_paper() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((20, 20), (10, 2));
+    let decoded = codec.decode(&bits.window((7, 3), (8, 8))).unwrap();
+
+    let geometry = GridGeometry::default().with_origin((20.0, 30.0));
+    assert_close(decoded.position_mm(&geometry), (22.1, 30.9));
+}
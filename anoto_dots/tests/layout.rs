@@ -0,0 +1,56 @@
+use anoto_dots::address::DotAddress;
+use anoto_dots::anoto_6x6_a4_fixed;
+use anoto_dots::geometry::GridGeometry;
+use anoto_dots::layout::{Margins, PageLayout, PaperSize, PatternRect};
+
+#[test]
+fn presets_fit_whole_dots_inside_the_margins() {
+    assert_eq!(PageLayout::a4().shape(), (923, 633));
+    assert_eq!(PageLayout::a5().shape(), (633, 426));
+    assert_eq!(PageLayout::letter().shape(), (864, 653));
+
+    // 180 mm is exactly 600 pitches despite rounding
+    let square = PageLayout::new(
+        PaperSize::Custom { width_mm: 200.0, height_mm: 200.0 },
+        Margins::uniform(10.0),
+        600,
+        GridGeometry::default(),
+    );
+    assert_eq!(square.shape(), (600, 600));
+    assert_eq!(PageLayout::new(PaperSize::A4.landscape(), Margins::uniform(0.0), 1200, GridGeometry::default()).shape(), (700, 990));
+}
+
+#[test]
+fn page_geometry_places_dots_inside_the_margins() {
+    let layout = PageLayout::a4();
+    assert_eq!(layout.pixel_size(), (4961, 7016));
+    assert!((layout.pitch_px() - 7.0866).abs() < 1e-3);
+
+    let grid = layout.page_grid();
+    let first = grid.grid_point((0, 0));
+    let (rows, cols) = layout.shape();
+    let last = grid.grid_point((cols as i32 - 1, rows as i32 - 1));
+    assert!((first.0 - 10.15).abs() < 1e-9 && (first.1 - 10.15).abs() < 1e-9);
+    assert!(last.0 < 200.0 && last.1 < 287.0);
+}
+
+#[test]
+fn pages_encode_their_pattern_rectangle() {
+    let codec = anoto_6x6_a4_fixed();
+    let layout = PageLayout::a5();
+    let origin = codec.dot_address((4, 9), (10_000, 20_000));
+
+    let rect = layout.pattern_rect(origin);
+    assert_eq!(rect.shape(), layout.shape());
+    assert!(rect.contains(origin.offset(425, 632)));
+    assert!(!rect.contains(origin.offset(426, 0)));
+
+    let page = layout.encode_page(&codec, origin).unwrap();
+    assert_eq!(page.shape(), layout.shape());
+    assert_eq!(codec.decode_address(&page.window((400, 600), (6, 6))).unwrap(), origin.offset(400, 600));
+
+    let next = PatternRect { origin: origin.offset(426, 0), ..rect };
+    assert!(!rect.overlaps(&next));
+    assert!(rect.overlaps(&PatternRect { origin: origin.offset(425, 632), width: 1, height: 1 }));
+    assert!(layout.encode_page(&codec, DotAddress::new(codec.section_size() - 10, 0)).is_none());
+}
@@ -0,0 +1,73 @@
+use anoto_dots::builder::CodecError;
+use anoto_dots::lookup::{WindowIndex, find_linear};
+use anoto_dots::{AnotoCodec, DecodingError, ERASED, anoto_6x6_a4_fixed};
+
+// Deterministic pseudo-random numbers below n
+struct Lcg(u64);
+
+impl Lcg {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % n as u64) as usize
+    }
+}
+
+// Windows of seq with some values erased or changed, plus random needles
+fn needles(seq: &[i8], order: usize, base: usize, rng: &mut Lcg) -> Vec<Vec<i8>> {
+    let mut needles = Vec::new();
+    for len in [order - 1, order, order + 2] {
+        for _ in 0..300 {
+            let start = rng.below(seq.len());
+            let mut needle: Vec<i8> = (0..len).map(|i| seq[(start + i) % seq.len()]).collect();
+            for _ in 0..rng.below(3) {
+                let i = rng.below(len);
+                needle[i] = if rng.below(2) == 0 { ERASED } else { rng.below(base) as i8 };
+            }
+            needles.push(needle);
+            needles.push((0..len).map(|_| rng.below(base) as i8).collect());
+        }
+    }
+    needles
+}
+
+#[test]
+fn index_finds_the_same_positions_as_a_linear_scan() {
+    let spec = anoto_6x6_a4_fixed().to_spec("anoto");
+    let mut rng = Lcg(7);
+    let mut sequences = vec![(spec.mns.clone(), spec.mns_order, 2)];
+    for (sns, &factor) in spec.sns.iter().zip(&spec.pfactors) {
+        sequences.push((sns.clone(), spec.mns_order - 1, factor as usize));
+    }
+    // Random sequences repeat their windows, every occurrence is still found
+    for (len, order, base) in [(40, 3, 2), (60, 2, 4)] {
+        sequences.push(((0..len).map(|_| rng.below(base) as i8).collect(), order, base));
+    }
+
+    for (seq, order, base) in sequences {
+        let index = WindowIndex::new(&seq, order).unwrap();
+        for needle in needles(&seq, order, base, &mut rng) {
+            let expected = find_linear(&seq, &needle);
+            assert_eq!(index.find_all(&needle), expected, "needle {:?}", needle);
+            assert_eq!(index.find(&needle), expected.first().copied());
+            match index.find_unique(&needle) {
+                Ok(found) => assert!(expected.len() <= 1 && found == expected.first().copied()),
+                Err(e) => assert!(e == DecodingError::Ambiguous && expected.len() > 1),
+            }
+        }
+    }
+}
+
+#[test]
+fn oversized_tables_are_rejected() {
+    let err = WindowIndex::new(&[0, 1, 1, 0], 64).err().unwrap();
+    assert_eq!(err, CodecError::OrderTooLarge { order: 64, alphabet: 2 });
+    assert!(WindowIndex::new(&[0, 2, 1], 13).is_err());
+    assert!(WindowIndex::new(&[0, 2, 1], 12).is_ok());
+
+    // A single one among zeros has distinct windows of any order
+    let mut mns = vec![0; 21];
+    mns[0] = 1;
+    let err = AnotoCodec::builder().mns(mns, 21).validate().unwrap_err();
+    assert_eq!(err, CodecError::OrderTooLarge { order: 21, alphabet: 2 });
+    assert_eq!(err.to_string(), "Lookup table for order 21 over 2 values exceeds 1048576 entries");
+}
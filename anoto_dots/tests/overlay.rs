@@ -0,0 +1,136 @@
+mod common;
+
+use anoto_dots::address::DotAddress;
+use anoto_dots::anoto_6x6_a4_fixed;
+use anoto_dots::geometry::GridGeometry;
+use anoto_dots::layout::{Margins, PageLayout, PaperSize};
+use anoto_dots::overlay::{Artwork, Layering, overlay_page, overlay_raster};
+use anoto_dots::pdf::PdfDocument;
+use anoto_dots::raster::{RasterOptions, render_raster};
+use common::{find, temp_path};
+use flate2::read::ZlibDecoder;
+use std::io::Read;
+
+// Blank artwork with its left half filled in red
+fn half_red(width: u32, height: u32) -> Artwork {
+    let rgb = (0..height)
+        .flat_map(|_| (0..width).flat_map(move |x| if x < width / 2 { [200, 0, 0] } else { [255, 255, 255] }))
+        .collect();
+    Artwork::from_rgb(width, height, rgb).unwrap()
+}
+
+#[test]
+fn layering_decides_whether_artwork_hides_dots() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((20, 20), (3, 4));
+    let raster = render_raster(&bits, &RasterOptions::default());
+    let (width, height) = (raster.width(), raster.height());
+    // Artwork at half the resolution is stretched over the raster
+    let artwork = half_red(width / 2, height / 2);
+    let black = |image: &Artwork, xs: std::ops::Range<u32>| {
+        (0..height).flat_map(|y| xs.clone().map(move |x| (x, y)))
+            .filter(|&(x, y)| image.pixel(x, y) == [0, 0, 0])
+            .count()
+    };
+    let dots = |xs: std::ops::Range<u32>| {
+        (0..height).flat_map(|y| xs.clone().map(move |x| (x, y)))
+            .filter(|&(x, y)| raster.is_black(x, y))
+            .count()
+    };
+    let left = 0..(width / 2) / 2 * 2;
+    let right = left.end..width;
+
+    let over = overlay_raster(&raster, &artwork, Layering::PatternOver);
+    assert_eq!((over.width(), over.height()), (width, height));
+    assert_eq!(black(&over, 0..width), raster.black_pixels());
+
+    let under = overlay_raster(&raster, &artwork, Layering::PatternUnder);
+    assert_eq!(black(&under, left.clone()), 0);
+    assert_eq!(black(&under, right.clone()), dots(right));
+    assert_eq!(under.pixel(0, 0), [200, 0, 0]);
+    assert!(dots(left) > 0);
+}
+
+#[test]
+fn artwork_roundtrips_through_png() {
+    let artwork = half_red(31, 7);
+    let path = temp_path("artwork.png");
+    artwork.save_png(&path, 300).unwrap();
+    let loaded = Artwork::load_png(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, artwork);
+
+    // One-bit pattern images load as black and white artwork
+    let codec = anoto_6x6_a4_fixed();
+    let raster = render_raster(&codec.encode_bitmatrix((9, 16), (10, 2)), &RasterOptions::default());
+    let path = temp_path("pattern.png");
+    std::fs::write(&path, raster.to_png().unwrap()).unwrap();
+    let loaded = Artwork::load_png(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!((loaded.width(), loaded.height()), (raster.width(), raster.height()));
+    let black = loaded.rgb().chunks(3).filter(|p| *p == [0, 0, 0]).count();
+    assert_eq!(black, raster.black_pixels());
+    assert!(Artwork::from_rgb(2, 2, vec![0; 11]).is_none());
+}
+
+#[test]
+fn pdf_pages_carry_masked_artwork_in_layer_order() {
+    let codec = anoto_6x6_a4_fixed();
+    let layout = PageLayout::a5();
+    let bits = codec.encode_bitmatrix((20, 20), (10, 2));
+    let artwork = half_red(40, 60);
+
+    let mut document = PdfDocument::new();
+    document.add_page_with_artwork(&layout, &bits, 0.1, &artwork, Layering::PatternUnder);
+    document.add_page(&layout, &bits, 0.1);
+    document.add_page_with_artwork(&layout, &bits, 0.1, &artwork, Layering::PatternOver);
+    let pdf = document.to_bytes().unwrap();
+
+    assert!(find(&pdf, b"/Kids [3 0 R 6 0 R 8 0 R] /Count 3", 0).is_some());
+    assert!(find(&pdf, b"/Resources << /XObject << /Art 5 0 R >> >>", 0).is_some());
+    assert!(find(&pdf, b"/Resources << /XObject << /Art 10 0 R >> >>", 0).is_some());
+    assert!(find(&pdf, b"/Width 40 /Height 60 /ColorSpace /DeviceRGB /BitsPerComponent 8 /Mask [250 255 250 255 250 255]", 0).is_some());
+
+    // Every cross-reference entry points at its object
+    let startxref = find(&pdf, b"startxref\n", 0).unwrap();
+    let xref: usize = std::str::from_utf8(&pdf[startxref + 10..pdf.len() - 7]).unwrap().parse().unwrap();
+    let table = std::str::from_utf8(&pdf[xref..startxref]).unwrap();
+    assert!(table.starts_with("xref\n0 11\n"));
+    for (id, line) in table.lines().skip(3).take(10).enumerate() {
+        let offset: usize = line[..10].parse().unwrap();
+        assert!(pdf[offset..].starts_with(format!("{} 0 obj", id + 1).as_bytes()));
+    }
+
+    let stream = |id: usize| {
+        let object = find(&pdf, format!("\n{} 0 obj", id).as_bytes(), 0).unwrap();
+        let start = find(&pdf, b"stream\n", object).unwrap() + 7;
+        let end = find(&pdf, b"\nendstream", start).unwrap();
+        let mut data = Vec::new();
+        ZlibDecoder::new(&pdf[start..end]).read_to_end(&mut data).unwrap();
+        data
+    };
+    let image = b"q 419.5276 0 0 595.2756 0 0 cm /Art Do Q\n";
+    let under = stream(4);
+    assert!(under.starts_with(b"q\n") && under.ends_with(image));
+    let over = stream(9);
+    assert!(over.starts_with(image) && over.ends_with(b"S\nQ\n"));
+    assert_eq!(stream(5), artwork.rgb());
+}
+
+#[test]
+fn pages_are_composited_at_printer_resolution() {
+    let codec = anoto_6x6_a4_fixed();
+    let layout = PageLayout::new(
+        PaperSize::Custom { width_mm: 20.0, height_mm: 10.0 },
+        Margins::uniform(1.0),
+        600,
+        GridGeometry::default(),
+    );
+    let bits = layout.encode_page(&codec, DotAddress::new(0, 0)).unwrap();
+    let page = overlay_page(&layout, &bits, &Artwork::blank(1, 1), Layering::PatternUnder);
+    assert_eq!((page.width(), page.height()), layout.pixel_size());
+
+    let black = page.rgb().chunks(3).filter(|p| *p == [0, 0, 0]).count();
+    let (rows, cols) = layout.shape();
+    assert_eq!(black, rows * cols * 4);
+}
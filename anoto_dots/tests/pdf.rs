@@ -0,0 +1,59 @@
+mod common;
+
+use anoto_dots::anoto_6x6_a4_fixed;
+use anoto_dots::layout::PageLayout;
+use anoto_dots::pdf::{PdfDocument, save_page_pdf};
+use common::{find, temp_path};
+use flate2::read::ZlibDecoder;
+use std::io::Read;
+
+#[test]
+fn pages_have_exact_paper_size_and_one_dot_per_bit() {
+    let codec = anoto_6x6_a4_fixed();
+    let mut document = PdfDocument::new();
+    for layout in [PageLayout::a4(), PageLayout::letter()] {
+        let mut bits = codec.encode_bitmatrix((40, 30), (1, 1));
+        bits.set_dot(0, 0, None);
+        document.add_page(&layout, &bits, 0.1);
+    }
+    assert_eq!(document.page_count(), 2);
+
+    let pdf = document.to_bytes().unwrap();
+    assert!(pdf.starts_with(b"%PDF-1.4\n"));
+    assert!(pdf.ends_with(b"%%EOF\n"));
+    assert!(find(&pdf, b"/MediaBox [0 0 595.2756 841.8898]", 0).is_some());
+    assert!(find(&pdf, b"/MediaBox [0 0 612 792]", 0).is_some());
+
+    // Every cross-reference entry points at its object
+    let startxref = find(&pdf, b"startxref\n", 0).unwrap();
+    let xref: usize = std::str::from_utf8(&pdf[startxref + 10..pdf.len() - 7]).unwrap().parse().unwrap();
+    let table = std::str::from_utf8(&pdf[xref..startxref]).unwrap();
+    for (id, line) in table.lines().skip(3).take(6).enumerate() {
+        let offset: usize = line[..10].parse().unwrap();
+        assert!(pdf[offset..].starts_with(format!("{} 0 obj", id + 1).as_bytes()));
+    }
+
+    // The first content stream draws every dot but the erased one in pure black
+    let start = find(&pdf, b"stream\n", 0).unwrap() + 7;
+    let end = find(&pdf, b"\nendstream", start).unwrap();
+    let mut content = String::new();
+    ZlibDecoder::new(&pdf[start..end]).read_to_string(&mut content).unwrap();
+    assert_eq!(content.lines().filter(|l| l.ends_with(" l")).count(), 40 * 30 - 1);
+    assert!(content.contains("0 0 0 1 K 1 J 0.1 w"));
+    assert!(content.ends_with("S\n"));
+}
+
+#[test]
+fn single_pages_are_saved_to_file() {
+    let codec = anoto_6x6_a4_fixed();
+    let layout = PageLayout::a5();
+    let bits = codec.encode_bitmatrix((20, 20), (10, 2));
+
+    let path = temp_path("page.pdf");
+    save_page_pdf(&layout, &bits, 0.12, &path).unwrap();
+    let pdf = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(find(&pdf, b"/Count 1", 0).is_some());
+    assert!(find(&pdf, b"/MediaBox [0 0 419.5276 595.2756]", 0).is_some());
+}
@@ -0,0 +1,294 @@
+mod common;
+
+use anoto_dots::anoto_6x6_a4_fixed;
+use anoto_dots::layout::PageLayout;
+use anoto_dots::overlay::{Artwork, Layering};
+use anoto_dots::pdf::PdfDocument;
+use anoto_dots::pdf_import::ImportedPage;
+use common::{find, temp_path};
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use std::io::{Read, Write};
+
+fn compress(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn stream(dict: &str, data: &[u8]) -> Vec<u8> {
+    let mut object = format!("<< {} /Length {} >>\nstream\n", dict, data.len()).into_bytes();
+    object.extend_from_slice(data);
+    object.extend_from_slice(b"\nendstream");
+    object
+}
+
+// PDF file of numbered objects with a cross-reference table
+fn pdf(objects: &[(usize, Vec<u8>)], trailer: &str) -> Vec<u8> {
+    let mut out = b"%PDF-1.5\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::new();
+    for (id, body) in objects {
+        offsets.push((*id, out.len()));
+        out.extend_from_slice(format!("{} 0 obj\n", id).as_bytes());
+        out.extend_from_slice(body);
+        out.extend_from_slice(b"\nendobj\n");
+    }
+    let xref = out.len();
+    out.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1).as_bytes());
+    for (_, offset) in offsets {
+        out.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    out.extend_from_slice(format!("trailer\n{}\nstartxref\n{}\n%%EOF\n", trailer, xref).as_bytes());
+    out
+}
+
+// Three pages inheriting their media box and resources from the page tree,
+// with the font in an object stream
+fn sample_pdf() -> Vec<u8> {
+    let font = b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>";
+    let mut objstm = b"9 0 ".to_vec();
+    let first = objstm.len();
+    objstm.extend_from_slice(font);
+
+    pdf(
+        &[
+            (1, b"<< /Type /Catalog /Pages 2 0 R >>".to_vec()),
+            (2, b"<< /Type /Pages /Kids [3 0 R 4 0 R 11 0 R] /Count 3 /MediaBox [10 20 310 420] /Resources 5 0 R >>".to_vec()),
+            (3, b"<< /Type /Page /Parent 2 0 R /Contents 6 0 R >>".to_vec()),
+            (4, b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 200.5 100] /Contents [7 0 R 8 0 R] >>".to_vec()),
+            (5, b"<< /Font << /F1 9 0 R >> /ProcSet [/PDF /Text] % comment\n>>".to_vec()),
+            (6, stream("", b"BT /F1 12 Tf (a \\) b) Tj ET")),
+            (7, stream("/Filter /FlateDecode", &compress(b"0 0 1 rg"))),
+            (8, stream("", b"0 0 50 50 re f")),
+            (10, stream(&format!("/Type /ObjStm /N 1 /First {} /Filter [/FlateDecode]", first), &compress(&objstm))),
+            (11, b"<< /Type /Page /Parent 2 0 R /Rotate 90 /Contents 8 0 R >>".to_vec()),
+        ],
+        "<< /Size 12 /Root 1 0 R >>",
+    )
+}
+
+// Decoded stream of object id
+fn object_stream(pdf: &[u8], id: usize) -> Vec<u8> {
+    let object = find(pdf, format!("\n{} 0 obj", id).as_bytes(), 0).unwrap();
+    let start = find(pdf, b"stream\n", object).unwrap() + 7;
+    let end = find(pdf, b"\nendstream", start).unwrap();
+    let mut data = Vec::new();
+    ZlibDecoder::new(&pdf[start..end]).read_to_end(&mut data).unwrap();
+    data
+}
+
+// Every cross-reference entry points at its object
+fn assert_xref(pdf: &[u8], objects: usize) {
+    let startxref = find(pdf, b"startxref\n", 0).unwrap();
+    let xref: usize = std::str::from_utf8(&pdf[startxref + 10..pdf.len() - 7]).unwrap().parse().unwrap();
+    let table = std::str::from_utf8(&pdf[xref..startxref]).unwrap();
+    assert!(table.starts_with(&format!("xref\n0 {}\n", objects + 1)));
+    for (id, line) in table.lines().skip(3).take(objects).enumerate() {
+        let offset: usize = line[..10].parse().unwrap();
+        assert!(pdf[offset..].starts_with(format!("{} 0 obj", id + 1).as_bytes()));
+    }
+}
+
+#[test]
+fn pages_are_read_with_inherited_attributes() {
+    let data = sample_pdf();
+
+    let page = ImportedPage::from_bytes(&data, 0).unwrap();
+    assert_eq!(page.media_box(), [10.0, 20.0, 310.0, 420.0]);
+    assert_eq!(page.size_pt(), (300.0, 400.0));
+    assert_eq!(page.content(), b"BT /F1 12 Tf (a \\) b) Tj ET\n");
+    assert_eq!(page.object_count(), 1);
+
+    let page = ImportedPage::from_bytes(&data, 1).unwrap();
+    assert_eq!(page.size_pt(), (200.5, 100.0));
+    assert_eq!(page.content(), b"0 0 1 rg\n0 0 50 50 re f\n");
+
+    let rotated = ImportedPage::from_bytes(&data, 2).unwrap_err();
+    assert_eq!(rotated.to_string(), "Rotated pages are not supported");
+    let missing = ImportedPage::from_bytes(&data, 3).unwrap_err();
+    assert_eq!(missing.to_string(), "PDF has 3 pages, no page 3");
+}
+
+#[test]
+fn imported_pages_become_form_xobjects_with_their_resources() {
+    let codec = anoto_6x6_a4_fixed();
+    let layout = PageLayout::a5();
+    let bits = codec.encode_bitmatrix((20, 20), (10, 2));
+    let page = ImportedPage::from_bytes(&sample_pdf(), 0).unwrap();
+
+    let mut document = PdfDocument::new();
+    document.add_page_with_artwork(&layout, &bits, 0.1, &page, Layering::PatternUnder);
+    document.add_page(&layout, &bits, 0.1);
+    let pdf = document.to_bytes().unwrap();
+    assert_xref(&pdf, 8);
+
+    // The page, its content, the form and the copied font, then the next page
+    assert!(find(&pdf, b"/Kids [3 0 R 7 0 R] /Count 2", 0).is_some());
+    assert!(find(&pdf, b"/Resources << /XObject << /Art 5 0 R >> >> /Contents 4 0 R", 0).is_some());
+    assert!(find(
+        &pdf,
+        b"5 0 obj\n<< /Type /XObject /Subtype /Form /BBox [10 20 310 420] /Resources << /Font << /F1 6 0 R >> /ProcSet [/PDF /Text] >> /Length",
+        0
+    ).is_some());
+    assert!(find(&pdf, b"6 0 obj\n<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>\nendobj", 0).is_some());
+    assert_eq!(object_stream(&pdf, 5), page.content());
+
+    // The media box is stretched over the A5 paper, on top of the dots
+    let content = object_stream(&pdf, 4);
+    assert!(content.starts_with(b"q\n"));
+    assert!(content.ends_with(b"S\nQ\nq 1.3984 0 0 1.4882 -13.9843 -29.7638 cm /Art Do Q\n"));
+}
+
+#[test]
+fn pages_written_by_pdf_document_can_be_imported() {
+    let codec = anoto_6x6_a4_fixed();
+    let layout = PageLayout::a5();
+    let bits = codec.encode_bitmatrix((20, 20), (10, 2));
+    let rgb = (0..40 * 60).flat_map(|i| [(i % 256) as u8, 0, 255]).collect();
+    let artwork = Artwork::from_rgb(40, 60, rgb).unwrap();
+
+    let mut document = PdfDocument::new();
+    document.add_page(&layout, &bits, 0.1);
+    document.add_page_with_artwork(&layout, &bits, 0.1, &artwork, Layering::PatternOver);
+    let path = temp_path("import.pdf");
+    document.save(&path).unwrap();
+    let page = ImportedPage::load(&path, 1).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(page.size_pt(), (419.5276, 595.2756));
+    assert_eq!(page.object_count(), 1);
+
+    // The image is copied with its compressed data, and imported pages can
+    // be imported again with their forms
+    let mut document = PdfDocument::new();
+    document.add_page_with_artwork(&layout, &bits, 0.1, &page, Layering::PatternOver);
+    let pdf = document.to_bytes().unwrap();
+    assert_xref(&pdf, 6);
+    assert!(find(&pdf, b"/Resources << /XObject << /Art 6 0 R >> >>", 0).is_some());
+    assert_eq!(object_stream(&pdf, 6), artwork.rgb());
+
+    let again = ImportedPage::from_bytes(&pdf, 0).unwrap();
+    assert_eq!(again.object_count(), 2);
+    assert!(again.content().starts_with(b"q 1 0 0 1 0 0 cm /Art Do Q\nq\n"));
+}
+
+#[test]
+fn unreadable_files_are_rejected() {
+    assert_eq!(ImportedPage::from_bytes(b"GIF89a", 0).unwrap_err().to_string(), "Not a PDF file");
+
+    let encrypted = pdf(
+        &[(1, b"<< /Type /Catalog /Pages 2 0 R >>".to_vec()), (2, b"<< /Type /Pages /Kids [] /Count 0 >>".to_vec())],
+        "<< /Size 3 /Root 1 0 R /Encrypt << /Filter /Standard >> >>",
+    );
+    assert_eq!(
+        ImportedPage::from_bytes(&encrypted, 0).unwrap_err().to_string(),
+        "Encrypted PDFs are not supported"
+    );
+
+    let no_box = pdf(
+        &[
+            (1, b"<< /Type /Catalog /Pages 2 0 R >>".to_vec()),
+            (2, b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec()),
+            (3, b"<< /Type /Page /Parent 2 0 R >>".to_vec()),
+        ],
+        "<< /Size 4 /Root 1 0 R >>",
+    );
+    assert_eq!(ImportedPage::from_bytes(&no_box, 0).unwrap_err().to_string(), "Page has no MediaBox");
+}
+
+// A single page drawing content, with the font of its resources defined by
+// the given objects
+fn page_with_font(content: Vec<u8>, font_objects: &[(usize, Vec<u8>)]) -> Vec<u8> {
+    let mut objects = vec![
+        (1, b"<< /Type /Catalog /Pages 2 0 R >>".to_vec()),
+        (2, b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec()),
+        (3, b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 100 100] /Resources << /Font << /F1 9 0 R >> >> /Contents 4 0 R >>".to_vec()),
+        (4, content),
+    ];
+    objects.extend_from_slice(font_objects);
+    pdf(&objects, "<< /Size 11 /Root 1 0 R >>")
+}
+
+#[test]
+fn malformed_object_streams_are_skipped() {
+    let content = stream("", b"0 0 m");
+    let font = b"9 0 << /Type /Font >>".to_vec();
+    for dict in [
+        "/Type /ObjStm /N 1 /First 1e30",
+        "/Type /ObjStm /N 1 /First 99999999999",
+        "/Type /ObjStm /N 1 /First -4",
+        "/Type /ObjStm /First 4",
+        "/Type /ObjStm /N 1 /First 4 /Filter /FlateDecode",
+    ] {
+        let data = page_with_font(content.clone(), &[(10, stream(dict, &font))]);
+        let page = ImportedPage::from_bytes(&data, 0).unwrap();
+        assert_eq!(page.object_count(), 0, "{}", dict);
+        assert_eq!(page.content(), b"0 0 m\n");
+    }
+
+    // Offsets past the end skip single objects, the others are still read
+    let objstm = b"8 4000000000 9 0 << /Type /Font >>".to_vec();
+    let data = page_with_font(content, &[(10, stream("/Type /ObjStm /N 2 /First 17", &objstm))]);
+    assert_eq!(ImportedPage::from_bytes(&data, 0).unwrap().object_count(), 1);
+}
+
+#[test]
+fn stream_lengths_are_not_trusted() {
+    let content = b"BT /F1 12 Tf (stream) Tj ET";
+    for length in ["9999", "-5", "3", "12 0 R", "1e3"] {
+        let mut object = format!("<< /Length {} >>\nstream\n", length).into_bytes();
+        object.extend_from_slice(content);
+        object.extend_from_slice(b"\r\nendstream");
+        let data = page_with_font(object, &[(9, b"<< /Type /Font >>".to_vec())]);
+
+        let page = ImportedPage::from_bytes(&data, 0).unwrap();
+        assert_eq!(page.content(), [content.as_slice(), b"\n"].concat(), "/Length {}", length);
+        assert_eq!(page.object_count(), 1);
+    }
+}
+
+#[test]
+fn broken_cross_reference_tables_are_ignored() {
+    let data = sample_pdf();
+    let expected = ImportedPage::from_bytes(&data, 1).unwrap();
+
+    // Offsets and startxref pointing nowhere
+    let xref = find(&data, b"xref\n", 0).unwrap();
+    let mut broken = data[..xref].to_vec();
+    broken.extend_from_slice(b"xref\n0 3\n0000000000 65535 f \n9999999999 00000 n \nabc\ntrailer\n<< /Size 12 /Root 1 0 R >>\nstartxref\n123456789\n%%EOF\n");
+    assert_eq!(ImportedPage::from_bytes(&broken, 1).unwrap(), expected);
+
+    // No table and no trailer, the catalog is found by its type
+    assert_eq!(ImportedPage::from_bytes(&data[..xref], 1).unwrap(), expected);
+}
+
+#[test]
+fn oversized_streams_are_rejected() {
+    let content = stream("/Filter /FlateDecode", &compress(&vec![b' '; (32 << 20) + 1]));
+    let data = page_with_font(content, &[]);
+    assert_eq!(
+        ImportedPage::from_bytes(&data, 0).unwrap_err().to_string(),
+        "PDF stream decodes to more than 33554432 bytes"
+    );
+}
+
+#[test]
+fn truncated_and_corrupted_files_do_not_panic() {
+    let data = sample_pdf();
+    for end in 0..data.len() {
+        let _ = ImportedPage::from_bytes(&data[..end], 0);
+    }
+
+    let mut seed = 1u64;
+    for _ in 0..2000 {
+        let mut corrupted = data.clone();
+        for _ in 0..4 {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let i = (seed >> 33) as usize % corrupted.len();
+            corrupted[i] = b"0123456789 /<>[]()R\\obj"[(seed >> 20) as usize % 23];
+        }
+        for page in 0..3 {
+            let _ = ImportedPage::from_bytes(&corrupted, page);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+#![cfg(feature = "plotting")]
+
+mod common;
+
+use anoto_dots::anoto_6x6_a4_fixed;
+use anoto_dots::bitmatrix::Dot;
+use anoto_dots::plotting::{DotColours, RenderOptions, draw_dots};
+use common::temp_path;
+use plotters::style::colors::{BLACK, RED};
+
+// (width, height) from the IHDR chunk of a PNG file
+fn png_size(png: &[u8]) -> (u32, u32) {
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&png[12..16], b"IHDR");
+    let read = |at: usize| u32::from_be_bytes(png[at..at + 4].try_into().unwrap());
+    (read(16), read(20))
+}
+
+#[test]
+fn image_fits_the_matrix_by_default() {
+    let plain = RenderOptions {
+        axes: false,
+        caption: None,
+        pixels_per_pitch: 10,
+        ..RenderOptions::default()
+    };
+    assert_eq!(plain.image_size((9, 16)), (17 * 10 + 30, 10 * 10 + 30));
+    assert_eq!(plain.image_size((60, 80)), (81 * 10 + 30, 61 * 10 + 30));
+
+    // Axes and caption make room for themselves
+    let decorated = RenderOptions { pixels_per_pitch: 10, ..RenderOptions::default() };
+    assert_eq!(decorated.image_size((9, 16)), (17 * 10 + 70, 10 * 10 + 125));
+
+    let fixed = RenderOptions { size: Some((800, 400)), ..RenderOptions::default() };
+    assert_eq!(fixed.image_size((60, 80)), (800, 400));
+}
+
+#[test]
+fn large_matrices_are_drawn_without_clipping() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((40, 60), (5, 5));
+    let options = RenderOptions {
+        pixels_per_pitch: 8,
+        dot_radius: 2,
+        colours: DotColours::Monochrome(BLACK),
+        axes: false,
+        caption: None,
+        save_bitmatrix: false,
+        ..RenderOptions::default()
+    };
+
+    let path = temp_path("plot.png");
+    draw_dots(&bits, &options, &path).unwrap();
+    let png = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(png_size(&png), options.image_size((40, 60)));
+    assert_eq!(png_size(&png), (61 * 8 + 30, 41 * 8 + 30));
+}
+
+#[test]
+fn colours_follow_the_dot_direction() {
+    let monochrome = DotColours::Monochrome(RED);
+    assert_eq!(monochrome.colour(Dot::Up), RED);
+    assert_eq!(monochrome.colour(Dot::Down), RED);
+
+    let per_direction = DotColours::default();
+    assert_eq!(per_direction.colour(Dot::Up), BLACK);
+    assert_eq!(per_direction.colour(Dot::Right), RED);
+}
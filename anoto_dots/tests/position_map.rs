@@ -0,0 +1,47 @@
+use anoto_dots::anoto_6x6_a4_fixed;
+use anoto_dots::bitmatrix::BitMatrix;
+
+#[test]
+fn clean_pattern_maps_every_dot() {
+    let codec = anoto_6x6_a4_fixed();
+    let origin = (300, 1200);
+    let bits = codec.encode_region(origin, (14, 20), (10, 2));
+
+    let map = codec.decode_position_map(&bits);
+    assert_eq!(map.positions.dim(), (14, 20));
+    assert!(map.inconsistent_cells().is_empty());
+    for y in 0..14 {
+        for x in 0..20 {
+            assert_eq!(map.position(y, x), Some((300 + x as i32, 1200 + y as i32)));
+        }
+    }
+}
+
+#[test]
+fn matrices_smaller_than_a_window_map_nothing() {
+    let codec = anoto_6x6_a4_fixed();
+    let map = codec.decode_position_map(&codec.encode_bitmatrix((5, 30), (10, 2)));
+    assert!(map.positions.iter().all(|p| p.is_none()));
+    assert!(map.inconsistent_cells().is_empty());
+}
+
+#[test]
+fn pasted_foreign_patch_is_flagged() {
+    let codec = anoto_6x6_a4_fixed();
+    let mut bits = codec.encode_bitmatrix((24, 24), (10, 2)).into_array();
+    let foreign = codec.encode_region((5000, 7000), (8, 8), (10, 2)).into_array();
+    for y in 0..8 {
+        for x in 0..8 {
+            for b in 0..2 {
+                bits[[y + 8, x + 8, b]] = foreign[[y, x, b]];
+            }
+        }
+    }
+
+    let map = codec.decode_position_map(&BitMatrix::new(bits).unwrap());
+    assert!(!map.is_consistent(10, 10));
+    assert!(map.is_consistent(0, 0));
+    assert!(map.is_consistent(23, 23));
+    assert_eq!(map.position(0, 0), Some((0, 0)));
+    assert!(map.inconsistent_cells().iter().all(|&(y, x)| (2..22).contains(&y) && (2..22).contains(&x)));
+}
@@ -0,0 +1,142 @@
+use anoto_dots::anoto_6x6_a4_fixed;
+use anoto_dots::bitmatrix::{BitMatrix, Dot};
+use anoto_dots::geometry::GridGeometry;
+use anoto_dots::layout::PageLayout;
+use anoto_dots::raster::{RasterOptions, render_page, render_raster};
+use flate2::Crc;
+use flate2::read::ZlibDecoder;
+use ndarray::Array2;
+use std::io::Read;
+
+fn black_pixels_in(raster: &anoto_dots::raster::Raster, xs: std::ops::Range<u32>, ys: std::ops::Range<u32>) -> usize {
+    ys.flat_map(|y| xs.clone().map(move |x| (x, y)))
+        .filter(|&(x, y)| raster.is_black(x, y))
+        .count()
+}
+
+#[test]
+fn dots_land_on_exact_pixels() {
+    let dots = Array2::from_shape_vec((2, 2), vec![
+        Some(Dot::Up), Some(Dot::Right),
+        Some(Dot::Left), Some(Dot::Down),
+    ]).unwrap();
+    // 10 pixels per millimetre, a pitch of 10 and a displacement of 2 pixels
+    let options = RasterOptions {
+        geometry: GridGeometry::new(1.0, 0.2, (1.0, 1.0)),
+        dpi: 254,
+        dot_size_px: 2,
+        size_px: Some((40, 40)),
+    };
+
+    let raster = render_raster(&BitMatrix::from_dots(&dots), &options);
+    assert_eq!((raster.width(), raster.height(), raster.dpi()), (40, 40, 254));
+    assert_eq!(raster.black_pixels(), 16);
+    assert_eq!(black_pixels_in(&raster, 9..11, 7..9), 4);
+    assert_eq!(black_pixels_in(&raster, 21..23, 9..11), 4);
+    assert_eq!(black_pixels_in(&raster, 7..9, 19..21), 4);
+    assert_eq!(black_pixels_in(&raster, 19..21, 21..23), 4);
+}
+
+#[test]
+fn dot_size_follows_the_resolution() {
+    let codec = anoto_6x6_a4_fixed();
+    let mut bits = codec.encode_bitmatrix((9, 16), (10, 2));
+    bits.set_dot(4, 4, None);
+
+    let raster = render_raster(&bits, &RasterOptions::default());
+    assert_eq!((raster.width(), raster.height()), (113, 64));
+    assert_eq!(raster.black_pixels(), (9 * 16 - 1) * 2 * 2);
+
+    // Five pixels across at 1200 DPI, with the corners rounded off
+    let options = RasterOptions { dpi: 1200, dot_size_px: 5, ..RasterOptions::default() };
+    let raster = render_raster(&bits, &options);
+    assert_eq!((raster.width(), raster.height()), (227, 128));
+    assert_eq!(raster.black_pixels(), (9 * 16 - 1) * 21);
+}
+
+#[test]
+fn png_is_one_bit_grayscale_at_the_printer_resolution() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((20, 20), (10, 2));
+    let raster = render_page(&PageLayout::a5(), &bits);
+    let png = raster.to_png().unwrap();
+
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    let mut chunks = Vec::new();
+    let mut at = 8;
+    while at < png.len() {
+        let len = u32::from_be_bytes(png[at..at + 4].try_into().unwrap()) as usize;
+        let kind = &png[at + 4..at + 8];
+        let data = &png[at + 8..at + 8 + len];
+        let mut crc = Crc::new();
+        crc.update(kind);
+        crc.update(data);
+        assert_eq!(crc.sum().to_be_bytes(), png[at + 8 + len..at + 12 + len]);
+        chunks.push((kind.to_vec(), data.to_vec()));
+        at += 12 + len;
+    }
+    let kinds: Vec<&[u8]> = chunks.iter().map(|(k, _)| k.as_slice()).collect();
+    assert_eq!(kinds, vec![&b"IHDR"[..], b"pHYs", b"IDAT", b"IEND"]);
+
+    // A5 at 600 DPI, one bit per pixel, 23622 pixels per metre
+    let header = &chunks[0].1;
+    assert_eq!(header[..8], [0, 0, 0x0d, 0xa8, 0, 0, 0x13, 0x61]);
+    assert_eq!(header[8..], [1, 0, 0, 0, 0]);
+    assert_eq!(chunks[1].1, [0, 0, 0x5c, 0x46, 0, 0, 0x5c, 0x46, 1]);
+
+    let mut pixels = Vec::new();
+    ZlibDecoder::new(&chunks[2].1[..]).read_to_end(&mut pixels).unwrap();
+    let stride = 3496usize.div_ceil(8);
+    assert_eq!(pixels.len(), 4961 * (stride + 1));
+    let black: usize = pixels.chunks(stride + 1)
+        .map(|row| {
+            assert_eq!(row[0], 0);
+            row[1..].iter().map(|b| b.count_zeros() as usize).sum::<usize>()
+        })
+        .sum();
+    assert_eq!(black, raster.black_pixels());
+    assert_eq!(black, 20 * 20 * 4);
+}
+
+#[test]
+fn tiff_holds_the_same_pixels() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((30, 30), (1, 1));
+    let options = RasterOptions { dpi: 1200, dot_size_px: 5, ..RasterOptions::default() };
+    let raster = render_raster(&bits, &options);
+    let tiff = raster.to_tiff();
+
+    let u16_at = |at: usize| u16::from_le_bytes(tiff[at..at + 2].try_into().unwrap());
+    let u32_at = |at: usize| u32::from_le_bytes(tiff[at..at + 4].try_into().unwrap());
+    assert_eq!(&tiff[..4], b"II*\0");
+    let ifd = u32_at(4) as usize;
+    let tags: Vec<(u16, u32)> = (0..u16_at(ifd) as usize)
+        .map(|i| {
+            let entry = ifd + 2 + 12 * i;
+            let value = if u16_at(entry + 2) == 3 { u16_at(entry + 8) as u32 } else { u32_at(entry + 8) };
+            (u16_at(entry), value)
+        })
+        .collect();
+    let tag = |t: u16| tags.iter().find(|(k, _)| *k == t).unwrap().1;
+    assert_eq!((tag(256), tag(257)), (raster.width(), raster.height()));
+    assert_eq!((tag(258), tag(259), tag(262)), (1, 32773, 0));
+    let resolution = tag(282) as usize;
+    assert_eq!((u32_at(resolution), u32_at(resolution + 4)), (1200, 1));
+
+    // Unpack the PackBits strip
+    let strip = &tiff[tag(273) as usize..(tag(273) + tag(279)) as usize];
+    let mut data = Vec::new();
+    let mut i = 0;
+    while i < strip.len() {
+        let n = strip[i] as i8;
+        if n >= 0 {
+            data.extend_from_slice(&strip[i + 1..i + 2 + n as usize]);
+            i += 2 + n as usize;
+        } else {
+            data.extend(std::iter::repeat_n(strip[i + 1], (1 - n as isize) as usize));
+            i += 2;
+        }
+    }
+    let expected: Vec<u8> = (0..raster.height()).flat_map(|y| raster.row(y).to_vec()).collect();
+    assert_eq!(data, expected);
+}
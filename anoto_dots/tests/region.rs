@@ -0,0 +1,63 @@
+use anoto_dots::anoto_6x6_a4_fixed;
+
+#[test]
+fn region_matches_slice_of_full_bitmatrix() {
+    let codec = anoto_6x6_a4_fixed();
+    let full = codec.encode_bitmatrix((150, 140), (10, 2));
+
+    for (x, y) in [(0, 0), (1, 0), (0, 1), (63, 64), (100, 37), (120, 130)] {
+        let region = codec.encode_region((x, y), (20, 20), (10, 2));
+        let expected = full.window((x, y), (20, 20));
+        assert_eq!(region, expected, "region at ({}, {})", x, y);
+    }
+}
+
+#[test]
+fn region_deep_in_the_pattern_decodes_to_its_origin() {
+    let codec = anoto_6x6_a4_fixed();
+    let origin = (25_000, 40_321);
+    let region = codec.encode_region(origin, (10, 12), (33, 4));
+
+    for (dx, dy) in [(0, 0), (6, 4), (3, 2)] {
+        let window = region.window((dx, dy), (6, 6));
+        let pos = codec.decode_position(&window).unwrap();
+        assert_eq!(pos, ((origin.0 + dx) as i32, (origin.1 + dy) as i32));
+        assert_eq!(codec.decode_section(&window, pos).unwrap(), (33, 4));
+    }
+}
+
+#[test]
+fn empty_region_has_no_dots() {
+    let codec = anoto_6x6_a4_fixed();
+    assert_eq!(codec.encode_region((5, 5), (0, 3), (0, 0)).shape(), (0, 3));
+}
+
+#[test]
+fn region_after_several_sns_periods_matches_full_bitmatrix() {
+    let codec = anoto_6x6_a4_fixed();
+    let full = codec.encode_bitmatrix((1100, 1100), (7, 50));
+
+    let region = codec.encode_region((1000, 1050), (6, 40), (7, 50));
+    assert_eq!(region, full.window((1000, 1050), (6, 40)));
+}
+
+#[test]
+fn negative_positions_continue_the_pattern_periodically() {
+    let codec = anoto_6x6_a4_fixed();
+    let period = codec.section_size() as i32;
+    let bits = codec.encode_bitmatrix((20, 20), (10, 2));
+    let sections = |pos: (i32, i32)| {
+        let window = bits.window((3, 5), (6, 6));
+        codec.decode_section(&window, pos).unwrap()
+    };
+
+    // Shifting a position by a whole period changes the section by the same
+    // amount on either side of zero
+    for (x, y) in [(3, 5), (0, 0), (17, 40)] {
+        let (before, here, after) = (sections((x - period, y - period)), sections((x, y)), sections((x + period, y + period)));
+        let step = |a: i32, b: i32| (b - a).rem_euclid(codec.section_count() as i32);
+        assert_eq!(step(before.0, here.0), step(here.0, after.0));
+        assert_eq!(step(before.1, here.1), step(here.1, after.1));
+    }
+    assert!(codec.decode_section(&bits.window((0, 0), (6, 6)), (-1, -7)).is_ok());
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use anoto_dots::address::DotAddress;
+use anoto_dots::allocator::PatternAllocator;
+use anoto_dots::anoto_6x6_a4_fixed;
+use anoto_dots::layout::PageLayout;
+use anoto_dots::registry::{PageRegistry, RegistryError};
+use common::temp_path;
+
+#[test]
+fn decoded_dots_resolve_to_their_page() {
+    let codec = anoto_6x6_a4_fixed();
+    let layout = PageLayout::a5();
+    let mut allocator = PatternAllocator::new(&codec);
+    let mut registry = PageRegistry::new();
+
+    for doc in ["minutes", "sketches"] {
+        for page in 1..=20 {
+            let rect = allocator.allocate(&format!("{}/{}", doc, page), layout.shape()).unwrap();
+            registry.register(doc, page, layout, rect.origin).unwrap();
+        }
+    }
+
+    let page = &registry.pages()[27];
+    assert_eq!((page.document_id.as_str(), page.page_number), ("sketches", 8));
+    let bits = layout.encode_page(&codec, page.rect.origin).unwrap();
+
+    let window = bits.window((100, 300), (6, 6));
+    let position = codec.decode_position(&window).unwrap();
+    let section = codec.decode_section(&window, position).unwrap();
+
+    let hit = registry.resolve(&codec, section, position).unwrap();
+    assert_eq!((hit.document_id, hit.page_number), ("sketches", 8));
+    assert!((hit.local_mm.0 - 40.15).abs() < 1e-9);
+    assert!((hit.local_mm.1 - 100.15).abs() < 1e-9);
+
+    assert!(registry.resolve_address(page.rect.origin.offset(426, 0)).is_some_and(|h| h.page_number == 9));
+    assert!(registry.resolve_address(DotAddress::new(0, 10_000_000)).is_none());
+}
+
+#[test]
+fn duplicate_and_overlapping_pages_are_refused() {
+    let layout = PageLayout::a4();
+    let mut registry = PageRegistry::new();
+    registry.register("doc", 1, layout, DotAddress::new(0, 0)).unwrap();
+
+    assert_eq!(
+        registry.register("doc", 1, layout, DotAddress::new(5000, 0)).unwrap_err(),
+        RegistryError::DuplicatePage { document_id: "doc".to_string(), page_number: 1 }
+    );
+    assert_eq!(
+        registry.register("other", 1, layout, DotAddress::new(600, 900)).unwrap_err(),
+        RegistryError::Overlap { document_id: "doc".to_string(), page_number: 1 }
+    );
+    assert!(registry.register("other", 1, layout, DotAddress::new(633, 900)).is_ok());
+}
+
+#[test]
+fn registry_survives_a_restart() {
+    let layout = PageLayout::letter();
+    let mut registry = PageRegistry::new();
+    for page in 0..5 {
+        registry.register("doc", page, layout, DotAddress::new(page as u64 * 1000, 0)).unwrap();
+    }
+
+    let path = temp_path("registry.json");
+    PageRegistry::new().save(&path).unwrap();
+    registry.save(&path).unwrap();
+    assert!(!std::path::Path::new(&format!("{}.tmp", path)).exists());
+    let loaded = PageRegistry::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.pages(), registry.pages());
+    assert_eq!(loaded.resolve_address(DotAddress::new(3500, 10)).unwrap().page_number, 3);
+}
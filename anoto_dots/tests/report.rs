@@ -0,0 +1,50 @@
+use anoto_dots::bitmatrix::BitMatrix;
+use anoto_dots::{Coordinate, DecodingError, anoto_6x6_a4_fixed};
+
+#[test]
+fn successful_decode_reports_every_step() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((20, 20), (10, 2));
+    let window = bits.window((7, 3), (6, 6));
+
+    let report = codec.decode_with_report(&window);
+    assert!(report.is_ok());
+    assert_eq!(report.position, Some((7, 3)));
+    assert_eq!(report.section, Some((10, 2)));
+
+    for axis in [&report.x, &report.y] {
+        assert_eq!(axis.locations.len(), 6);
+        assert!(axis.locations.iter().all(|l| l.len() == 1));
+        assert_eq!(axis.deltas.len(), 5);
+        assert!(axis.deltas.iter().all(|d| d.is_some_and(|d| (5..=58).contains(&d))));
+        assert_eq!(axis.coefficients.len(), 5);
+        assert!(axis.coefficients.iter().all(|c| c.len() == 4));
+        assert_eq!(axis.sns_positions.len(), 4);
+    }
+    assert_eq!(report.x.position, Some(7));
+    assert_eq!(report.y.position, Some(3));
+}
+
+#[test]
+fn failed_decode_reports_where_it_stopped() {
+    let codec = anoto_6x6_a4_fixed();
+    let mut bits = codec.encode_bitmatrix((6, 6), (10, 2)).into_array();
+    bits[[0, 3, 0]] = 1 - bits[[0, 3, 0]];
+    let window = BitMatrix::new(bits).unwrap();
+
+    let report = codec.decode_with_report(&window);
+    let expected = DecodingError::DeltaOutOfRange { axis: Coordinate::X, row: 3, value: 1 };
+    assert_eq!(report.error, Some(expected.clone()));
+    assert_eq!(codec.decode_position(&window).unwrap_err(), expected);
+
+    assert_eq!(report.x.deltas[2], Some(1));
+    assert!(report.x.coefficients.is_empty());
+    assert!(report.x.sns_positions.is_empty());
+    assert_eq!(report.x.position, None);
+    assert!(report.y.locations.is_empty());
+    assert_eq!(report.position, None);
+
+    let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+    assert_eq!(json["error"]["DeltaOutOfRange"]["value"], 1);
+    assert_eq!(json["x"]["locations"].as_array().unwrap().len(), 6);
+}
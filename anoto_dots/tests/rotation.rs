@@ -0,0 +1,74 @@
+use anoto_dots::bitmatrix::BitMatrix;
+use anoto_dots::{DecodingError, anoto_6x6_a4_fixed, rot90};
+use ndarray::Array3;
+
+#[test]
+fn rot90_turns_back_after_four_quarters() {
+    let codec = anoto_6x6_a4_fixed();
+    let mut bits = codec.encode_bitmatrix((7, 9), (3, 4));
+    bits.set_dot(2, 5, None);
+
+    for k in 0..4 {
+        let turned = rot90(&bits, k);
+        let shape = if k % 2 == 0 { (7, 9) } else { (9, 7) };
+        assert_eq!(turned.shape(), shape);
+        assert_eq!(rot90(&turned, 4 - k), bits);
+        assert_eq!(rot90(&bits, k + 4), turned);
+    }
+}
+
+#[test]
+fn every_quarter_turn_is_recovered() {
+    let codec = anoto_6x6_a4_fixed();
+    for (origin, section) in [((0, 0), (0, 0)), ((5000, 123456), (10, 2)), ((1000000, 77), (33, 61))] {
+        let bits = codec.encode_region(origin, (30, 30), section);
+        for y in (0..=22).step_by(2) {
+            for x in (0..=22).step_by(2) {
+                let window = bits.window((x, y), (8, 8));
+                for k in 0..4 {
+                    let turned = rot90(&window, k);
+                    assert_eq!(codec.decode_rotation(&turned), Ok((4 - k) % 4));
+
+                    let decoded = codec.decode(&turned).unwrap();
+                    let position = ((origin.0 + x) as i32, (origin.1 + y) as i32);
+                    assert_eq!(decoded.position, position);
+                    assert_eq!(decoded.section, section);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn small_windows_are_ambiguous_but_never_wrong() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((30, 30), (10, 2));
+    let (mut recovered, mut ambiguous) = (0, 0);
+
+    for y in 0..=24 {
+        for x in 0..=24 {
+            let window = bits.window((x, y), (6, 6));
+            for k in 0..4 {
+                match codec.decode_rotation(&rot90(&window, k)) {
+                    Ok(r) => {
+                        assert_eq!(r, (4 - k) % 4);
+                        recovered += 1;
+                    }
+                    Err(e) => {
+                        assert_eq!(e, DecodingError::RotationAmbiguous);
+                        ambiguous += 1;
+                    }
+                }
+            }
+        }
+    }
+    assert!(recovered > 0 && ambiguous > 0);
+}
+
+#[test]
+fn windows_without_pattern_have_no_rotation() {
+    let codec = anoto_6x6_a4_fixed();
+    let blank = BitMatrix::new(Array3::zeros((8, 8, 2))).unwrap();
+    assert_eq!(codec.decode_rotation(&blank), Err(DecodingError::NoRotation));
+    assert!(codec.decode(&blank).is_err());
+}
@@ -0,0 +1,57 @@
+use anoto_dots::anoto_6x6_a4_fixed;
+
+#[test]
+fn every_x_section_roundtrips() {
+    let codec = anoto_6x6_a4_fixed();
+    for sx in 0..63 {
+        codec.verify_roundtrip((12, 12), (sx, 17)).unwrap();
+    }
+}
+
+#[test]
+fn every_y_section_roundtrips() {
+    let codec = anoto_6x6_a4_fixed();
+    for sy in 0..63 {
+        codec.verify_roundtrip((12, 12), (40, sy)).unwrap();
+    }
+}
+
+#[test]
+fn window_origins_across_a_full_mns_period_roundtrip() {
+    let codec = anoto_6x6_a4_fixed();
+    for section in [(0, 0), (10, 2), (62, 62), (31, 7)] {
+        codec.verify_roundtrip((70, 70), section).unwrap();
+    }
+}
+
+#[test]
+fn distant_windows_decode_their_section() {
+    let codec = anoto_6x6_a4_fixed();
+    let bits = codec.encode_bitmatrix((200, 300), (57, 20));
+
+    for (x, y) in [(0usize, 0usize), (150, 3), (17, 190), (290, 190), (128, 64)] {
+        let window = bits.window((x, y), (6, 6));
+        let pos = codec.decode_position(&window).unwrap();
+        assert_eq!(pos, (x as i32, y as i32));
+        assert_eq!(codec.decode_section(&window, pos).unwrap(), (57, 20));
+    }
+}
+
+#[test]
+fn sections_are_taken_modulo_the_mns_length() {
+    let codec = anoto_6x6_a4_fixed();
+    let canonical = codec.encode_bitmatrix((9, 16), (57, 20));
+
+    assert_eq!(codec.encode_bitmatrix((9, 16), (120, 20)), canonical);
+    assert_eq!(codec.encode_bitmatrix((9, 16), (-6, -43)), canonical);
+
+    let window = canonical.window((7, 3), (6, 6));
+    let pos = codec.decode_position(&window).unwrap();
+    assert_eq!(codec.decode_section(&window, pos).unwrap(), (57, 20));
+}
+
+#[test]
+fn roundtrip_rejects_shapes_smaller_than_a_window() {
+    let codec = anoto_6x6_a4_fixed();
+    assert!(codec.verify_roundtrip((5, 12), (0, 0)).is_err());
+}
@@ -0,0 +1,58 @@
+use anoto_dots::sequences::{de_bruijn, find_cyclic_sequence, generate_codec};
+use std::collections::HashSet;
+
+fn has_unique_cyclic_windows(seq: &[i8], order: usize) -> bool {
+    let windows: HashSet<Vec<i8>> = (0..seq.len())
+        .map(|p| (0..order).map(|i| seq[(p + i) % seq.len()]).collect())
+        .collect();
+    windows.len() == seq.len()
+}
+
+#[test]
+fn de_bruijn_holds_every_window_once() {
+    for (alphabet, order) in [(2, 3), (2, 6), (3, 5), (4, 2)] {
+        let seq = de_bruijn(alphabet, order);
+        assert_eq!(seq.len(), alphabet.pow(order as u32));
+        assert!(has_unique_cyclic_windows(&seq, order));
+    }
+}
+
+#[test]
+fn cyclic_sequences_of_any_length_have_unique_windows() {
+    for (alphabet, order, length) in [(2, 6, 63), (2, 6, 40), (3, 5, 236), (3, 5, 233), (2, 5, 31), (3, 2, 7)] {
+        let seq = find_cyclic_sequence(alphabet, order, length).unwrap();
+        assert_eq!(seq.len(), length);
+        assert!(seq.iter().all(|&v| (v as usize) < alphabet));
+        assert!(has_unique_cyclic_windows(&seq, order), "{:?}", (alphabet, order, length));
+    }
+}
+
+#[test]
+fn impossible_lengths_are_rejected() {
+    assert_eq!(find_cyclic_sequence(2, 3, 9), None);
+    assert_eq!(find_cyclic_sequence(2, 3, 2), None);
+    assert_eq!(find_cyclic_sequence(1, 3, 3), None);
+}
+
+#[test]
+fn generated_anoto_sized_codec_roundtrips() {
+    let generated = generate_codec(6, &[3, 3, 2, 3]).unwrap();
+    assert_eq!(generated.mns.len(), 63);
+    assert_eq!(generated.delta_range, (5, 58));
+
+    let codec = generated.to_codec().unwrap();
+    codec.verify_roundtrip((16, 16), (12, 40)).unwrap();
+}
+
+#[test]
+fn generated_small_window_codec_roundtrips() {
+    let generated = generate_codec(4, &[2, 3]).unwrap();
+    let codec = generated.to_codec().unwrap();
+    codec.verify_roundtrip((20, 20), (3, 9)).unwrap();
+}
+
+#[test]
+fn generation_fails_when_factors_do_not_fit_the_mns() {
+    assert_eq!(generate_codec(3, &[2, 3, 2]), None);
+    assert_eq!(generate_codec(1, &[2]), None);
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use anoto_dots::{AnotoCodec, anoto_6x6_a4_fixed};
+use anoto_dots::builder::CodecError;
+use anoto_dots::spec::CodecSpec;
+use common::temp_path;
+
+#[test]
+fn exported_spec_rebuilds_an_equivalent_codec() {
+    let codec = anoto_6x6_a4_fixed();
+    let spec = codec.to_spec("anoto_6x6_a4_fixed");
+    assert_eq!(spec.mns_order, 6);
+    assert_eq!(spec.pfactors, vec![3, 3, 2, 3]);
+    assert_eq!(spec.delta_range, (5, 58));
+
+    let rebuilt = AnotoCodec::try_from(&spec).unwrap();
+    assert_eq!(
+        rebuilt.encode_bitmatrix((9, 16), (10, 2)),
+        codec.encode_bitmatrix((9, 16), (10, 2))
+    );
+}
+
+#[test]
+fn spec_roundtrips_through_json_and_toml() {
+    let spec = anoto_6x6_a4_fixed().to_spec("anoto_6x6_a4_fixed");
+
+    for name in ["spec.json", "spec.toml"] {
+        let path = temp_path(name);
+        spec.save(&path).unwrap();
+        let loaded = CodecSpec::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, spec, "{}", name);
+    }
+}
+
+#[test]
+fn spec_loads_from_handwritten_toml() {
+    let path = temp_path("toy.toml");
+    std::fs::write(&path, r#"
+name = "toy"
+mns = [0, 0, 1, 0, 1, 1, 1]
+mns_order = 3
+sns = [[0, 0, 1, 1], [0, 0, 1, 1, 2, 2, 0, 2, 1]]
+pfactors = [2, 3]
+delta_range = [1, 6]
+"#).unwrap();
+    let spec = CodecSpec::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let codec = spec.to_codec().unwrap();
+    codec.verify_roundtrip((10, 10), (2, 4)).unwrap();
+}
+
+#[test]
+fn invalid_spec_reports_codec_error() {
+    let mut spec = anoto_6x6_a4_fixed().to_spec("broken");
+    spec.delta_range = (5, 57);
+    assert!(matches!(spec.to_codec(), Err(CodecError::FactorProductMismatch { .. })));
+}
+
+#[test]
+fn loading_a_missing_file_fails() {
+    assert!(CodecSpec::load(&temp_path("missing.json")).is_err());
+}
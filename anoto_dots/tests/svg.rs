@@ -0,0 +1,55 @@
+use anoto_dots::anoto_6x6_a4_fixed;
+use anoto_dots::bitmatrix::{BitMatrix, Dot};
+use anoto_dots::geometry::GridGeometry;
+use anoto_dots::layout::PageLayout;
+use anoto_dots::svg::{SvgOptions, render_svg};
+use ndarray::Array2;
+
+#[test]
+fn every_dot_becomes_one_circle() {
+    let codec = anoto_6x6_a4_fixed();
+    let mut bits = codec.encode_bitmatrix((9, 16), (10, 2));
+    bits.set_dot(4, 4, None);
+
+    let svg = render_svg(&bits, &SvgOptions::default());
+    assert!(svg.contains("width=\"4.8mm\" height=\"2.7mm\" viewBox=\"0 0 4.8 2.7\""));
+    assert_eq!(svg.matches("<circle").count(), 9 * 16 - 1);
+    assert!(!svg.contains("<text") && !svg.contains("<line"));
+}
+
+#[test]
+fn circles_sit_at_displaced_grid_points() {
+    let dots = Array2::from_shape_vec((2, 2), vec![
+        Some(Dot::Up), Some(Dot::Right),
+        Some(Dot::Left), Some(Dot::Down),
+    ]).unwrap();
+    let options = SvgOptions {
+        geometry: GridGeometry::new(0.6, 0.25, (1.0, 2.0)),
+        dot_diameter_mm: 0.2,
+        size_mm: Some((10.0, 10.0)),
+    };
+
+    let svg = render_svg(&BitMatrix::from_dots(&dots), &options);
+    let circles: Vec<&str> = svg.lines().filter(|l| l.starts_with("<circle")).collect();
+    assert_eq!(circles, vec![
+        "<circle cx=\"1\" cy=\"1.85\" r=\"0.1\"/>",
+        "<circle cx=\"1.75\" cy=\"2\" r=\"0.1\"/>",
+        "<circle cx=\"0.85\" cy=\"2.6\" r=\"0.1\"/>",
+        "<circle cx=\"1.6\" cy=\"2.75\" r=\"0.1\"/>",
+    ]);
+    assert!(svg.contains("width=\"10mm\" height=\"10mm\""));
+}
+
+#[test]
+fn page_documents_have_the_paper_size() {
+    let codec = anoto_6x6_a4_fixed();
+    let layout = PageLayout::a5();
+    let bits = codec.encode_bitmatrix((3, 3), (10, 2));
+
+    let svg = render_svg(&bits, &SvgOptions::for_page(&layout));
+    assert!(svg.contains("width=\"148mm\" height=\"210mm\""));
+    let (dx, dy) = bits.dot(0, 0).unwrap().displacement();
+    let mm = |d: i32| ((10.15 + 0.05 * d as f64) * 1e4).round() / 1e4;
+    let expected = format!("<circle cx=\"{}\" cy=\"{}\" r=\"0.05\"/>", mm(dx), mm(dy));
+    assert_eq!(svg.lines().find(|l| l.starts_with("<circle")).unwrap(), expected);
+}